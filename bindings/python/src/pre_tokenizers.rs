@@ -6,7 +6,7 @@ use pyo3::exceptions;
 use pyo3::prelude::*;
 use pyo3::types::*;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use tk::tokenizer::{Offsets, Result};
+use tk::tokenizer::{get_range_of, OffsetReferential, Offsets, PreTokenizedString, Result};
 
 #[pyclass(dict, module = "tokenizers.pre_tokenizers")]
 pub struct PreTokenizer {
@@ -52,13 +52,21 @@ impl PreTokenizer {
     }
 
     fn pre_tokenize(&self, s: &str) -> PyResult<Vec<(String, Offsets)>> {
-        // TODO: Expose the NormalizedString
-        let mut normalized = tk::tokenizer::NormalizedString::from(s);
-        ToPyResult(
+        // TODO: Expose the PreTokenizedString
+        let mut pretokenized = PreTokenizedString::from(s);
+        let res: PyResult<()> = ToPyResult(
             self.pretok
-                .execute(|pretok| pretok.pre_tokenize(&mut normalized)),
+                .execute(|pretok| pretok.pre_tokenize(&mut pretokenized)),
         )
-        .into()
+        .into();
+        res?;
+
+        let splits: PyResult<Vec<_>> =
+            ToPyResult(pretokenized.get_splits(OffsetReferential::Normalized)).into();
+        Ok(splits?
+            .into_iter()
+            .map(|(s, offsets)| (s.to_owned(), offsets))
+            .collect())
     }
 }
 
@@ -221,33 +229,56 @@ impl PyPreTokenizer {
 
 #[typetag::serde]
 impl tk::tokenizer::PreTokenizer for PyPreTokenizer {
-    fn pre_tokenize(
-        &self,
-        sentence: &mut tk::tokenizer::NormalizedString,
-    ) -> Result<Vec<(String, Offsets)>> {
+    fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> Result<()> {
         let gil = Python::acquire_gil();
         let py = gil.python();
 
-        let args = PyTuple::new(py, &[sentence.get()]);
-        match self.class.call_method(py, "pre_tokenize", args, None) {
-            Ok(res) => Ok(res
-                .cast_as::<PyList>(py)
-                .map_err(|_| {
-                    PyError::from("`pre_tokenize is expected to return a List[(str, (uint, uint))]")
-                })?
-                .extract::<Vec<(String, Offsets)>>()
-                .map_err(|_| {
-                    PyError::from(
-                        "`pre_tokenize` is expected to return a List[(str, (uint, uint))]",
-                    )
-                })?),
-            Err(e) => {
-                e.print(py);
-                Err(Box::new(PyError::from(
-                    "Error while calling `pre_tokenize`",
-                )))
+        // The custom PreTokenizer is called on each split, and only the offsets it returns
+        // are used: it can't modify the splits, so each returned string must be the part of the
+        // split found at its offsets.
+        pretokenized.split(|_, normalized| {
+            let args = PyTuple::new(py, &[normalized.get()]);
+            match self.class.call_method(py, "pre_tokenize", args, None) {
+                Ok(res) => res
+                    .cast_as::<PyList>(py)
+                    .map_err(|_| {
+                        PyError::from(
+                            "`pre_tokenize is expected to return a List[(str, (uint, uint))]",
+                        )
+                    })?
+                    .extract::<Vec<(String, Offsets)>>()
+                    .map_err(|_| {
+                        PyError::from(
+                            "`pre_tokenize` is expected to return a List[(str, (uint, uint))]",
+                        )
+                    })?
+                    .into_iter()
+                    .map(|(piece, offsets)| {
+                        let found = get_range_of(normalized.get(), offsets.0..offsets.1);
+                        if found == Some(piece.as_str())
+                            || (piece.is_empty() && offsets.0 == offsets.1)
+                        {
+                            Ok(offsets)
+                        } else {
+                            Err(PyError(format!(
+                                "`pre_tokenize` returned {:?} at {:?}, but {:?} is found there: \
+                                 the pieces can't be modified",
+                                piece,
+                                offsets,
+                                found.unwrap_or("")
+                            ))
+                            .into())
+                        }
+                    })
+                    .collect(),
+                Err(e) => {
+                    e.print(py);
+                    Err(Box::new(PyError::from(
+                        "Error while calling `pre_tokenize`",
+                    )))
+                }
             }
-        }
+        })
     }
 }
 
@@ -70,3 +70,37 @@ class TestCharDelimiterSplit:
         assert isinstance(CharDelimiterSplit(" "), PreTokenizer)
         assert isinstance(CharDelimiterSplit(" "), CharDelimiterSplit)
         assert isinstance(pickle.loads(pickle.dumps(CharDelimiterSplit("-"))), CharDelimiterSplit)
+
+
+class TestCustomPreTokenizer:
+    class SplitOnDash:
+        def __init__(self, upper=False):
+            self.upper = upper
+
+        def pre_tokenize(self, sequence):
+            splits = []
+            start = 0
+            for i, c in enumerate(sequence + "-"):
+                if c == "-":
+                    piece = sequence[start:i]
+                    splits.append((piece.upper() if self.upper else piece, (start, i)))
+                    start = i + 1
+            return splits
+
+    def test_instantiate(self):
+        pretok = PreTokenizer.custom(TestCustomPreTokenizer.SplitOnDash())
+        assert isinstance(pretok, PreTokenizer)
+
+    def test_pre_tokenize(self):
+        pretok = PreTokenizer.custom(TestCustomPreTokenizer.SplitOnDash())
+        assert pretok.pre_tokenize("Hey-my-friend") == [
+            ("Hey", (0, 3)),
+            ("my", (4, 6)),
+            ("friend", (7, 13)),
+        ]
+
+    def test_modified_pieces(self):
+        # The pieces can't be modified, they must match the text found at their offsets
+        pretok = PreTokenizer.custom(TestCustomPreTokenizer.SplitOnDash(upper=True))
+        with pytest.raises(Exception, match="the pieces can't be modified"):
+            pretok.pre_tokenize("Hey-my-friend")
@@ -13,6 +13,21 @@ class PreTokenizer:
         """ Pre tokenize the given sequence """
         pass
 
+    @staticmethod
+    def custom(pretok) -> PreTokenizer:
+        """ Instantiate a PreTokenizer from a custom Python object
+
+        Args:
+            pretok: any object with a method `pre_tokenize(self, sequence: str)` returning a
+                `List[Tuple[str, Offsets]]`.
+
+                This method is called on each split of the input (the whole normalized input,
+                unless other pre-tokenizers split it before), and only the offsets it returns
+                are used, relative to the given sequence. The pieces can't be modified: each
+                returned string must be the text found at its offsets, or an error is raised.
+        """
+        pass
+
 class ByteLevel(PreTokenizer):
     """ ByteLevel PreTokenizer
 
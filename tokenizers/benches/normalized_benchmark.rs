@@ -0,0 +1,65 @@
+#[macro_use]
+extern crate criterion;
+
+use criterion::{black_box, Criterion};
+use tokenizers::tokenizer::{NormalizedString, Range};
+
+/// A large text with a few accents, so that most of the alignments are left untouched by the
+/// normalizations
+fn create_text() -> String {
+    let sentence = "The quick brown fox jumps over the lazy dog. Le café est très noir. ";
+    sentence.repeat(10_000)
+}
+
+fn bench_normalized(c: &mut Criterion) {
+    let text = create_text();
+    let len = text.chars().count();
+
+    c.bench_function("NormalizedString from", |b| {
+        b.iter(|| black_box(NormalizedString::from(text.as_str())))
+    });
+
+    c.bench_function("NormalizedString lowercase", |b| {
+        b.iter(|| {
+            let mut n = NormalizedString::from(text.as_str());
+            n.lowercase();
+            black_box(n)
+        })
+    });
+
+    c.bench_function("NormalizedString nfd + filter", |b| {
+        b.iter(|| {
+            let mut n = NormalizedString::from(text.as_str());
+            n.nfd().filter(|c| !c.is_ascii_punctuation());
+            black_box(n)
+        })
+    });
+
+    let mut normalized = NormalizedString::from(text.as_str());
+    normalized.nfd().filter(|c| !c.is_ascii_punctuation());
+    let normalized_len = normalized.len();
+
+    c.bench_function("NormalizedString slice", |b| {
+        b.iter(|| {
+            for start in (0..normalized_len / 2).step_by(normalized_len / 100) {
+                black_box(normalized.slice(Range::Normalized(start..start + 100)));
+            }
+        })
+    });
+
+    c.bench_function("NormalizedString convert_offsets", |b| {
+        b.iter(|| {
+            for start in (0..len / 2).step_by(len / 100) {
+                black_box(normalized.convert_offsets(Range::Original(start..start + 100)));
+                black_box(normalized.convert_offsets(Range::Normalized(start..start + 100)));
+            }
+        })
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(20);
+    targets = bench_normalized
+}
+criterion_main!(benches);
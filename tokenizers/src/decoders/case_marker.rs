@@ -0,0 +1,120 @@
+use crate::normalizers::case_marker::{ALL_CAPS, CAPITALIZED};
+use crate::tokenizer::{Decoder, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+/// Restores the casing removed by the `CaseMarker` normalizer. The tokens are first decoded
+/// with the wrapped `Decoder` (or joined with spaces if there is none), and then each marker is
+/// removed, uppercasing the following letter (or the following word for the `all_caps` marker).
+/// The whitespace between a marker and its letter, added by some decoders, is removed too.
+pub struct CaseMarkerDecoder {
+    capitalized: String,
+    all_caps: String,
+    decoder: Option<Box<dyn Decoder>>,
+}
+
+impl CaseMarkerDecoder {
+    pub fn new(decoder: Option<Box<dyn Decoder>>) -> Self {
+        Self {
+            capitalized: CAPITALIZED.into(),
+            all_caps: ALL_CAPS.into(),
+            decoder,
+        }
+    }
+
+    /// Set the markers, which must be the same as the ones used by the `CaseMarker` normalizer
+    pub fn markers(mut self, capitalized: String, all_caps: String) -> Self {
+        self.capitalized = capitalized;
+        self.all_caps = all_caps;
+        self
+    }
+
+    /// Restore the casing of the given text
+    fn restore(&self, text: &str) -> String {
+        let mut output = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(c) = rest.chars().next() {
+            let (marker, all_caps) = if rest.starts_with(&self.all_caps) {
+                (&self.all_caps, true)
+            } else if rest.starts_with(&self.capitalized) {
+                (&self.capitalized, false)
+            } else {
+                output.push(c);
+                rest = &rest[c.len_utf8()..];
+                continue;
+            };
+
+            rest = rest[marker.len()..].trim_start();
+            let mut uppercased = 0;
+            for c in rest.chars() {
+                if !c.is_alphabetic() || (uppercased > 0 && !all_caps) {
+                    break;
+                }
+                output.extend(c.to_uppercase());
+                uppercased += c.len_utf8();
+            }
+            rest = &rest[uppercased..];
+        }
+        output
+    }
+}
+
+impl Default for CaseMarkerDecoder {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+#[typetag::serde]
+impl Decoder for CaseMarkerDecoder {
+    fn decode(&self, tokens: Vec<String>) -> Result<String> {
+        let decoded = match &self.decoder {
+            Some(decoder) => decoder.decode(tokens)?,
+            None => tokens.join(" "),
+        };
+        Ok(self.restore(&decoded))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoders::wordpiece::WordPiece;
+
+    #[test]
+    fn decode() {
+        let decoder = CaseMarkerDecoder::default();
+        let tokens = ["⇧", "hello", "⇪", "world", "!", "i⇧phone"];
+        assert_eq!(
+            decoder
+                .decode(tokens.iter().map(|t| t.to_string()).collect())
+                .unwrap(),
+            "Hello WORLD ! iPhone"
+        );
+
+        let decoder = CaseMarkerDecoder::new(Some(Box::new(WordPiece::default())));
+        let tokens = ["⇪", "na", "##sa", "⇧", "launch", "##es", "!"];
+        assert_eq!(
+            decoder
+                .decode(tokens.iter().map(|t| t.to_string()).collect())
+                .unwrap(),
+            "NASA Launches!"
+        );
+    }
+
+    #[test]
+    fn serialization() {
+        let decoder = CaseMarkerDecoder::new(Some(Box::new(WordPiece::default())))
+            .markers("<c>".into(), "<C>".into());
+        let serialized = serde_json::to_string(&decoder).unwrap();
+        assert_eq!(
+            serialized,
+            r###"{"capitalized":"<c>","all_caps":"<C>","decoder":{"type":"WordPiece","prefix":"##","cleanup":true}}"###
+        );
+        let decoder: CaseMarkerDecoder = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(
+            decoder.decode(vec!["<c>".into(), "hello".into()]).unwrap(),
+            "Hello"
+        );
+    }
+}
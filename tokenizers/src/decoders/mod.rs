@@ -1,4 +1,5 @@
 pub mod bpe;
+pub mod case_marker;
 pub mod wordpiece;
 
 // Re-export these as decoders
@@ -2,10 +2,16 @@
 
 use super::{Pair, WithFirstLastIterator, Word, BPE};
 use crate::parallelism::*;
-use crate::tokenizer::{AddedToken, Model, Result, Trainer};
+use crate::tokenizer::{
+    AddedToken, CancellationToken, Model, ProgressCallback, Result, Trainer, TrainingPhase,
+    TrainingProgress,
+};
+use crate::utils::progress::Progress;
 use indicatif::{ProgressBar, ProgressStyle};
+use serde::{Deserialize, Serialize, Serializer};
 use std::cmp::Ordering;
 use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::sync::Arc;
 
 #[derive(Debug, Eq)]
 struct Merge {
@@ -43,6 +49,8 @@ struct Config {
     initial_alphabet: HashSet<char>,
     continuing_subword_prefix: Option<String>,
     end_of_word_suffix: Option<String>,
+    progress_callback: Option<ProgressCallback>,
+    cancellation_token: Option<CancellationToken>,
 }
 
 /// A `BpeTrainerBuilder` can be used to create a `BpeTrainer` with a custom
@@ -63,6 +71,8 @@ impl Default for BpeTrainerBuilder {
                 initial_alphabet: HashSet::new(),
                 continuing_subword_prefix: None,
                 end_of_word_suffix: None,
+                progress_callback: None,
+                cancellation_token: None,
             },
         }
     }
@@ -122,6 +132,21 @@ impl BpeTrainerBuilder {
         self
     }
 
+    /// Set the callback that will receive the progress events
+    pub fn progress_callback<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(TrainingProgress) + Send + Sync + 'static,
+    {
+        self.config.progress_callback = Some(Arc::new(callback));
+        self
+    }
+
+    /// Set the token that can be used to cancel the training
+    pub fn cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.config.cancellation_token = Some(token);
+        self
+    }
+
     /// Constructs the final BpeTrainer
     pub fn build(self) -> BpeTrainer {
         BpeTrainer {
@@ -133,10 +158,25 @@ impl BpeTrainerBuilder {
             initial_alphabet: self.config.initial_alphabet,
             continuing_subword_prefix: self.config.continuing_subword_prefix,
             end_of_word_suffix: self.config.end_of_word_suffix,
+            progress_callback: self.config.progress_callback,
+            cancellation_token: self.config.cancellation_token,
         }
     }
 }
 
+/// Serialize the alphabet sorted, to keep the output deterministic
+fn ordered_alphabet<S>(
+    alphabet: &HashSet<char>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut chars = alphabet.iter().collect::<Vec<_>>();
+    chars.sort_unstable();
+    chars.serialize(serializer)
+}
+
 /// In charge of training a `BPE` model from a mapping of words to word counts.
 ///
 /// # Examples
@@ -153,6 +193,7 @@ impl BpeTrainerBuilder {
 /// let trainer = BpeTrainer::default();
 /// let (model, special_tokens) = trainer.train(word_counts).unwrap();
 /// ```
+#[derive(Serialize, Deserialize)]
 pub struct BpeTrainer {
     /// The minimum frequency a pair must have to produce a merge operation
     min_frequency: u32,
@@ -166,11 +207,18 @@ pub struct BpeTrainer {
     limit_alphabet: Option<usize>,
     /// The initial alphabet we want absolutely to include. This allows to cover
     /// some characters that are not necessarily in the training set
+    #[serde(serialize_with = "ordered_alphabet")]
     initial_alphabet: HashSet<char>,
     /// An optional prefix to use on any subword that exist only behind another one
     continuing_subword_prefix: Option<String>,
     /// An optional suffix to caracterize and end-of-word subword
     end_of_word_suffix: Option<String>,
+    /// An optional callback that will receive the progress events
+    #[serde(skip)]
+    progress_callback: Option<ProgressCallback>,
+    /// An optional token that can be used to cancel the training
+    #[serde(skip)]
+    cancellation_token: Option<CancellationToken>,
 }
 
 impl Default for BpeTrainer {
@@ -192,9 +240,9 @@ impl BpeTrainer {
         BpeTrainerBuilder::new()
     }
 
-    /// Setup a progress bar if asked to show progress
-    fn setup_progress(&self) -> Option<ProgressBar> {
-        if self.show_progress {
+    /// Setup the progress, with a progress bar if asked to show progress
+    fn setup_progress(&self) -> Progress {
+        let bar = if self.show_progress {
             let p = ProgressBar::new(0);
             p.set_style(
                 ProgressStyle::default_bar()
@@ -203,28 +251,22 @@ impl BpeTrainer {
             Some(p)
         } else {
             None
-        }
+        };
+        Progress::new(
+            bar,
+            self.progress_callback.clone(),
+            self.cancellation_token.clone(),
+        )
     }
 
-    /// Set the progress bar in the finish state
-    fn finalize_progress(&self, p: &Option<ProgressBar>, final_len: usize) {
-        if let Some(p) = p {
-            p.set_length(final_len as u64);
-            p.finish();
+    /// Set the progress in the finish state
+    fn finalize_progress(&self, p: &mut Progress) {
+        p.finish();
+        if p.has_bar() {
             println!();
         }
     }
 
-    /// Update the progress bar with the new provided length and message
-    fn update_progress(&self, p: &Option<ProgressBar>, len: usize, message: &str) {
-        if let Some(p) = p {
-            p.set_message(message);
-            p.set_length(len as u64);
-            p.set_draw_delta(len as u64 / 100);
-            p.reset();
-        }
-    }
-
     /// Add the provided special tokens to the initial vocabulary
     fn add_special_tokens(&self, w2id: &mut HashMap<String, u32>, id2w: &mut Vec<String>) {
         for token in &self.special_tokens {
@@ -300,12 +342,13 @@ impl BpeTrainer {
         wc: &HashMap<String, u32>,
         w2id: &mut HashMap<String, u32>,
         id2w: &mut Vec<String>,
-        p: &Option<ProgressBar>,
-    ) -> (Vec<Word>, Vec<u32>) {
+        p: &Progress,
+    ) -> Result<(Vec<Word>, Vec<u32>)> {
         let mut words: Vec<Word> = Vec::with_capacity(wc.len());
         let mut counts: Vec<u32> = Vec::with_capacity(wc.len());
 
         for (word, count) in wc {
+            p.check()?;
             let mut current_word = Word::new();
             counts.push(*count);
 
@@ -337,19 +380,17 @@ impl BpeTrainer {
             }
             words.push(current_word);
 
-            if let Some(p) = p {
-                p.inc(1);
-            }
+            p.inc(1);
         }
 
-        (words, counts)
+        Ok((words, counts))
     }
 
     fn count_pairs(
         &self,
         words: &[Word],
         counts: &[u32],
-        p: &Option<ProgressBar>,
+        p: &Progress,
     ) -> (HashMap<Pair, i32>, HashMap<Pair, HashSet<usize>>) {
         words
             .maybe_par_iter()
@@ -357,6 +398,11 @@ impl BpeTrainer {
             .map(|(i, word)| {
                 let mut pair_counts = HashMap::new();
                 let mut where_to_update: HashMap<Pair, HashSet<usize>> = HashMap::new();
+                // The remaining words are skipped once cancelled, and the caller returns the
+                // error right after
+                if p.is_cancelled() {
+                    return (pair_counts, where_to_update);
+                }
 
                 for window in word.get_chars().windows(2) {
                     let cur_pair: Pair = (window[0], window[1]);
@@ -381,9 +427,7 @@ impl BpeTrainer {
                     *pair_counts.get_mut(&cur_pair).unwrap() += count as i32;
                 }
 
-                if let Some(p) = &p {
-                    p.inc(1);
-                }
+                p.inc(1);
 
                 (pair_counts, where_to_update)
             })
@@ -408,7 +452,7 @@ impl BpeTrainer {
         let mut word_to_id: HashMap<String, u32> = HashMap::with_capacity(self.vocab_size);
         let mut id_to_word: Vec<String> = Vec::with_capacity(self.vocab_size);
 
-        let progress = self.setup_progress();
+        let mut progress = self.setup_progress();
 
         //
         // 1. Add all special tokens to the vocabulary
@@ -423,16 +467,21 @@ impl BpeTrainer {
         //
         // 3. Tokenize words
         //
-        self.update_progress(&progress, word_counts.len(), "Tokenize words");
+        progress.start(
+            TrainingPhase::TokenizeWords,
+            word_counts.len() as u64,
+            "Tokenize words",
+        );
         let (words, counts) =
-            self.tokenize_words(&word_counts, &mut word_to_id, &mut id_to_word, &progress);
-        self.finalize_progress(&progress, words.len());
+            self.tokenize_words(&word_counts, &mut word_to_id, &mut id_to_word, &progress)?;
+        self.finalize_progress(&mut progress);
 
         //
         // 4. Count pairs in words
         //
-        self.update_progress(&progress, words.len(), "Count pairs");
+        progress.start(TrainingPhase::CountPairs, words.len() as u64, "Count pairs");
         let (mut pair_counts, mut where_to_update) = self.count_pairs(&words, &counts, &progress);
+        progress.check()?;
         // Insert them in the queue
         let mut queue = BinaryHeap::with_capacity(pair_counts.len());
         where_to_update.drain().for_each(|(pair, pos)| {
@@ -445,14 +494,20 @@ impl BpeTrainer {
                 });
             }
         });
-        self.finalize_progress(&progress, words.len());
+        self.finalize_progress(&mut progress);
 
         //
         // 5. Do merges
         //
-        self.update_progress(&progress, self.vocab_size, "Compute merges");
+        progress.start(
+            TrainingPhase::ComputeMerges,
+            self.vocab_size as u64,
+            "Compute merges",
+        );
         let mut merges: Vec<(Pair, u32)> = vec![];
         loop {
+            progress.check()?;
+
             // Stop as soon as we have a big enough vocabulary
             if word_to_id.len() >= self.vocab_size {
                 break;
@@ -545,11 +600,9 @@ impl BpeTrainer {
                 }
             });
 
-            if let Some(p) = &progress {
-                p.inc(1);
-            }
+            progress.inc(1);
         }
-        self.finalize_progress(&progress, merges.len());
+        self.finalize_progress(&mut progress);
 
         let mut builder = BPE::builder().vocab_and_merges(
             word_to_id,
@@ -574,6 +627,7 @@ impl BpeTrainer {
     }
 }
 
+#[typetag::serde]
 impl Trainer for BpeTrainer {
     /// Train a BPE model
     fn train(
@@ -598,12 +652,27 @@ impl Trainer for BpeTrainer {
     fn should_show_progress(&self) -> bool {
         self.show_progress
     }
+
+    /// The callback receiving the progress events
+    fn progress_callback(&self) -> Option<&ProgressCallback> {
+        self.progress_callback.as_ref()
+    }
+
+    /// The token used to cancel the training
+    fn cancellation_token(&self) -> Option<&CancellationToken> {
+        self.cancellation_token.as_ref()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{BpeTrainer, Pair};
+    use super::{BpeTrainer, Pair, Word};
+    use crate::tokenizer::{
+        AddedToken, CancellationToken, Trainer, TrainingCancelled, TrainingPhase,
+    };
+    use crate::utils::progress::Progress;
     use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
 
     #[test]
     fn test_train() {
@@ -677,4 +746,95 @@ mod tests {
         .collect();
         assert_eq!(model.merges, expected_merges);
     }
+
+    #[test]
+    fn test_serialization() {
+        let trainer = BpeTrainer::builder()
+            .vocab_size(100)
+            .initial_alphabet(['b', 'a'].iter().copied().collect())
+            .special_tokens(vec![AddedToken::from("[UNK]", true)])
+            .build();
+        let trainer: &dyn Trainer = &trainer;
+
+        let ser = serde_json::to_string(trainer).unwrap();
+        assert_eq!(
+            ser,
+            r#"{"type":"BpeTrainer","min_frequency":0,"vocab_size":100,"show_progress":true,"#
+                .to_owned()
+                + r#""special_tokens":[{"content":"[UNK]","single_word":false,"lstrip":false,"#
+                + r#""rstrip":false,"normalized":false}],"limit_alphabet":null,"#
+                + r#""initial_alphabet":["a","b"],"continuing_subword_prefix":null,"#
+                + r#""end_of_word_suffix":null}"#
+        );
+        let de: Box<dyn Trainer> = serde_json::from_str(&ser).unwrap();
+        assert_eq!(serde_json::to_string(&de).unwrap(), ser);
+    }
+
+    #[test]
+    fn test_train_progress() {
+        let word_counts: HashMap<String, u32> = [("roses".into(), 2), ("are".into(), 2)]
+            .iter()
+            .cloned()
+            .collect();
+        let phases = Arc::new(Mutex::new(vec![]));
+        let p = phases.clone();
+        let trainer = BpeTrainer::builder()
+            .show_progress(false)
+            .progress_callback(move |progress| {
+                let mut phases = p.lock().unwrap();
+                if phases.last() != Some(&progress.phase) {
+                    phases.push(progress.phase);
+                }
+            })
+            .build();
+        trainer.train(word_counts).unwrap();
+
+        assert_eq!(
+            *phases.lock().unwrap(),
+            vec![
+                TrainingPhase::TokenizeWords,
+                TrainingPhase::CountPairs,
+                TrainingPhase::ComputeMerges
+            ]
+        );
+    }
+
+    #[test]
+    fn test_train_cancelled() {
+        let word_counts: HashMap<String, u32> = [("roses".into(), 2), ("are".into(), 2)]
+            .iter()
+            .cloned()
+            .collect();
+        let token = CancellationToken::new();
+        let trainer = BpeTrainer::builder()
+            .show_progress(false)
+            .cancellation_token(token.clone())
+            .build();
+        token.cancel();
+
+        let err = trainer.train(word_counts).unwrap_err();
+        assert!(err.downcast_ref::<TrainingCancelled>().is_some());
+    }
+
+    #[test]
+    fn test_count_pairs_cancelled() {
+        let mut word = Word::new();
+        for c in &[0, 1, 2, 1, 2] {
+            word.add(*c);
+        }
+        let words = vec![word; 100];
+        let counts = vec![1; 100];
+        let trainer = BpeTrainer::default();
+
+        let token = CancellationToken::new();
+        let progress = Progress::new(None, None, Some(token.clone()));
+        let (pair_counts, _) = trainer.count_pairs(&words, &counts, &progress);
+        assert_eq!(pair_counts[&(1, 2)], 200);
+
+        // Once cancelled, the words are not counted anymore
+        token.cancel();
+        let (pair_counts, where_to_update) = trainer.count_pairs(&words, &counts, &progress);
+        assert!(pair_counts.is_empty());
+        assert!(where_to_update.is_empty());
+    }
 }
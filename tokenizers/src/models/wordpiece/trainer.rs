@@ -1,6 +1,9 @@
 use super::WordPiece;
 use crate::models::bpe::{BpeTrainer, BpeTrainerBuilder};
-use crate::tokenizer::{AddedToken, Model, Result, Trainer};
+use crate::tokenizer::{
+    AddedToken, CancellationToken, Model, ProgressCallback, Result, Trainer, TrainingProgress,
+};
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 
 /// A `WordPieceTrainerBuilder` can be used to create a `WordPieceTrainer` with a custom
@@ -71,6 +74,21 @@ impl WordPieceTrainerBuilder {
         self
     }
 
+    /// Set the callback that will receive the progress events
+    pub fn progress_callback<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(TrainingProgress) + Send + Sync + 'static,
+    {
+        self.bpe_trainer_builder = self.bpe_trainer_builder.progress_callback(callback);
+        self
+    }
+
+    /// Set the token that can be used to cancel the training
+    pub fn cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.bpe_trainer_builder = self.bpe_trainer_builder.cancellation_token(token);
+        self
+    }
+
     /// Constructs the final BpeTrainer
     pub fn build(self) -> WordPieceTrainer {
         let bpe_trainer = self.bpe_trainer_builder.build();
@@ -79,7 +97,7 @@ impl WordPieceTrainerBuilder {
 }
 
 /// Trains a `WordPiece` model.
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct WordPieceTrainer {
     bpe_trainer: BpeTrainer,
 }
@@ -95,6 +113,7 @@ impl WordPieceTrainer {
     }
 }
 
+#[typetag::serde]
 impl Trainer for WordPieceTrainer {
     fn train(
         &self,
@@ -111,4 +130,12 @@ impl Trainer for WordPieceTrainer {
     fn should_show_progress(&self) -> bool {
         self.bpe_trainer.should_show_progress()
     }
+
+    fn progress_callback(&self) -> Option<&ProgressCallback> {
+        self.bpe_trainer.progress_callback()
+    }
+
+    fn cancellation_token(&self) -> Option<&CancellationToken> {
+        self.bpe_trainer.cancellation_token()
+    }
 }
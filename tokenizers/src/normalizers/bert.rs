@@ -1,3 +1,4 @@
+use crate::normalizers::isolate::{isolate_chars, CharClass};
 use crate::tokenizer::{NormalizedString, Normalizer, Result};
 use serde::{Deserialize, Serialize};
 use unicode_categories::UnicodeCategories;
@@ -25,29 +26,6 @@ fn is_control(c: char) -> bool {
     }
 }
 
-/// Checks whether a character is chinese
-/// This defines a "chinese character" as anything in the CJK Unicode block:
-///   https://en.wikipedia.org/wiki/CJK_Unified_Ideographs_(Unicode_block)
-///
-/// Note that the CJK Unicode block is NOT all Japanese and Korean characters,
-/// despite its name. The modern Korean Hangul alphabet is a different block,
-/// as is Japanese Hiragana and Katakana. Those alphabets are used to write
-/// space-separated words, so they are not treated specially and handled
-/// like for all of the other languages.
-fn is_chinese_char(c: char) -> bool {
-    match c as usize {
-        0x4E00..=0x9FFF => true,
-        0x3400..=0x4DBF => true,
-        0x20000..=0x2A6DF => true,
-        0x2A700..=0x2B73F => true,
-        0x2B740..=0x2B81F => true,
-        0x2B920..=0x2CEAF => true,
-        0xF900..=0xFAFF => true,
-        0x2F800..=0x2FA1F => true,
-        _ => false,
-    }
-}
-
 #[derive(Serialize, Deserialize)]
 pub struct BertNormalizer {
     /// Whether to do the bert basic cleaning:
@@ -60,6 +38,9 @@ pub struct BertNormalizer {
     strip_accents: Option<bool>,
     /// Whether to lowercase the input
     lowercase: bool,
+    /// Some other classes of characters to put spaces around, just like the chinese characters
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    isolated_chars: Vec<CharClass>,
 }
 
 impl Default for BertNormalizer {
@@ -69,6 +50,7 @@ impl Default for BertNormalizer {
             handle_chinese_chars: true,
             strip_accents: None,
             lowercase: true,
+            isolated_chars: vec![],
         }
     }
 }
@@ -85,25 +67,28 @@ impl BertNormalizer {
             handle_chinese_chars,
             strip_accents,
             lowercase,
+            isolated_chars: vec![],
         }
     }
 
+    /// Set some other classes of characters to put spaces around. This is useful to reproduce
+    /// multilingual variants of BERT.
+    pub fn with_isolated_chars(mut self, isolated_chars: Vec<CharClass>) -> Self {
+        self.isolated_chars = isolated_chars;
+        self
+    }
+
     fn do_clean_text(&self, normalized: &mut NormalizedString) {
         normalized
             .filter(|c| !(c as usize == 0 || c as usize == 0xfffd || is_control(c)))
             .map(|c| if is_whitespace(c) { ' ' } else { c });
     }
 
-    fn do_handle_chinese_chars(&self, normalized: &mut NormalizedString) {
-        let mut new_chars: Vec<(char, isize)> = vec![];
-        normalized.for_each(|c| {
-            if is_chinese_char(c) {
-                new_chars.extend(&[(' ', 1), (c, 0), (' ', 1)]);
-            } else {
-                new_chars.push((c, 0));
-            }
+    fn do_isolate_chars(&self, normalized: &mut NormalizedString) {
+        isolate_chars(normalized, |c| {
+            (self.handle_chinese_chars && CharClass::Chinese.contains(c))
+                || self.isolated_chars.iter().any(|class| class.contains(c))
         });
-        normalized.transform(new_chars.into_iter(), 0);
     }
 
     fn do_strip_accents(&self, normalized: &mut NormalizedString) {
@@ -121,8 +106,8 @@ impl Normalizer for BertNormalizer {
         if self.clean_text {
             self.do_clean_text(normalized);
         }
-        if self.handle_chinese_chars {
-            self.do_handle_chinese_chars(normalized);
+        if self.handle_chinese_chars || !self.isolated_chars.is_empty() {
+            self.do_isolate_chars(normalized);
         }
         let strip_accents = self.strip_accents.unwrap_or(self.lowercase);
         if strip_accents {
@@ -135,3 +120,30 @@ impl Normalizer for BertNormalizer {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serialization() {
+        // The default is serialized just like before the isolated chars existed
+        let serialized = serde_json::to_string(&BertNormalizer::default()).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"clean_text":true,"handle_chinese_chars":true,"strip_accents":null,"lowercase":true}"#
+        );
+        let deserialized: BertNormalizer = serde_json::from_str(&serialized).unwrap();
+        assert!(deserialized.isolated_chars.is_empty());
+
+        let normalizer = BertNormalizer::default()
+            .with_isolated_chars(vec![CharClass::Thai, CharClass::Range(0x100, 0x17f)]);
+        let serialized = serde_json::to_string(&normalizer).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"clean_text":true,"handle_chinese_chars":true,"strip_accents":null,"lowercase":true,"isolated_chars":["Thai",{"Range":[256,383]}]}"#
+        );
+        let deserialized: BertNormalizer = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized.isolated_chars, normalizer.isolated_chars);
+    }
+}
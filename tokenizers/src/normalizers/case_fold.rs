@@ -0,0 +1,73 @@
+use crate::tokenizer::{NormalizedString, Normalizer, Result};
+use caseless::Caseless;
+use serde::{Deserialize, Serialize};
+
+/// Applies the Unicode full case folding (cf. `CaseFolding.txt`), which is better suited than
+/// lowercasing to match strings without caring about their case: `ß` becomes `ss`, the Greek
+/// final sigma `ς` becomes `σ`, ...
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CaseFold {
+    /// Whether to use the Turkic specific mappings, where `I` becomes the dotless `ı`, and the
+    /// dotted `İ` becomes `i`
+    turkic: bool,
+}
+
+impl CaseFold {
+    pub fn new(turkic: bool) -> Self {
+        Self { turkic }
+    }
+
+    fn fold(&self, c: char) -> Vec<char> {
+        match c {
+            'I' if self.turkic => vec!['ı'],
+            'İ' if self.turkic => vec!['i'],
+            c => std::iter::once(c).default_case_fold().collect(),
+        }
+    }
+}
+
+#[typetag::serde]
+impl Normalizer for CaseFold {
+    fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
+        let mut new_chars: Vec<(char, isize)> = vec![];
+        normalized.for_each(|c| {
+            new_chars.extend(
+                self.fold(c)
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, c)| (c, if index > 0 { 1 } else { 0 })),
+            );
+        });
+        normalized.transform(new_chars.into_iter(), 0);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Range;
+
+    #[test]
+    fn case_fold() {
+        let mut n = NormalizedString::from("Straße ΣΟΦΟΣ σοφος İstanbul");
+        CaseFold::default().normalize(&mut n).unwrap();
+
+        assert_eq!(n.get(), "strasse σοφοσ σοφοσ i\u{307}stanbul");
+        // The expansion of `ß` is aligned with it
+        assert_eq!(n.get_range_original(Range::Normalized(4..6)), Some("ß"));
+        assert_eq!(n.convert_offsets(Range::Original(7..12)), Some(8..13));
+        assert_eq!(n.get_range_original(Range::Normalized(20..22)), Some("İ"));
+    }
+
+    #[test]
+    fn turkic() {
+        let mut n = NormalizedString::from("DİYARBAKIR");
+        CaseFold::new(true).normalize(&mut n).unwrap();
+        assert_eq!(n.get(), "diyarbakır");
+
+        let mut n = NormalizedString::from("DİYARBAKIR");
+        CaseFold::new(false).normalize(&mut n).unwrap();
+        assert_eq!(n.get(), "di\u{307}yarbakir");
+    }
+}
@@ -0,0 +1,200 @@
+use crate::decoders::case_marker::CaseMarkerDecoder;
+use crate::tokenizer::{AddedToken, Decoder, NormalizedString, Normalizer, Result};
+use serde::{Deserialize, Serialize};
+
+/// The default marker for a capitalized letter
+pub(crate) static CAPITALIZED: &str = "⇧";
+/// The default marker for a word in all caps
+pub(crate) static ALL_CAPS: &str = "⇪";
+
+/// Returns the lowercase version of the given char, only if it is an uppercase char that we can
+/// restore exactly by uppercasing it again.
+fn reversible_lowercase(c: char) -> Option<char> {
+    if !c.is_uppercase() {
+        return None;
+    }
+    let mut lower = c.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(l), None) => {
+            let mut upper = l.to_uppercase();
+            match (upper.next(), upper.next()) {
+                (Some(u), None) if u == c => Some(l),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Pushes the given char prefixed with the marker, all aligned with the char
+fn push_marked(new_chars: &mut Vec<(char, isize)>, marker: &str, c: char) {
+    new_chars.extend(
+        marker
+            .chars()
+            .chain(std::iter::once(c))
+            .enumerate()
+            .map(|(i, c)| (c, if i > 0 { 1 } else { 0 })),
+    );
+}
+
+/// Lowercases the text while inserting markers, so that the original casing can be restored
+/// by the matching `CaseMarkerDecoder`:
+///   - A word in all caps (at least two letters) is prefixed with the `all_caps` marker
+///   - Any other uppercase letter is prefixed with the `capitalized` marker
+///
+/// The letters that can't be restored exactly by uppercasing them again (like `İ`) are kept
+/// as they are. Each marker is aligned with the letter it applies to.
+///
+/// The markers should be added to the `Tokenizer` (see `added_tokens`), so that they never get
+/// merged with the words.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CaseMarker {
+    capitalized: String,
+    all_caps: String,
+}
+
+impl Default for CaseMarker {
+    fn default() -> Self {
+        Self {
+            capitalized: CAPITALIZED.into(),
+            all_caps: ALL_CAPS.into(),
+        }
+    }
+}
+
+impl CaseMarker {
+    pub fn new(capitalized: String, all_caps: String) -> Self {
+        Self {
+            capitalized,
+            all_caps,
+        }
+    }
+
+    /// The markers, as `AddedToken` to be added to the `Tokenizer`. These are not special
+    /// tokens, so that `Tokenizer::decode` keeps them even when skipping the special tokens.
+    pub fn added_tokens(&self) -> Vec<AddedToken> {
+        vec![
+            AddedToken::from(self.capitalized.clone(), false),
+            AddedToken::from(self.all_caps.clone(), false),
+        ]
+    }
+
+    /// Returns the `CaseMarkerDecoder` restoring the casing, using the same markers, and
+    /// applied on the output of the given `Decoder`.
+    pub fn decoder(&self, decoder: Option<Box<dyn Decoder>>) -> CaseMarkerDecoder {
+        CaseMarkerDecoder::new(decoder).markers(self.capitalized.clone(), self.all_caps.clone())
+    }
+}
+
+#[typetag::serde]
+impl Normalizer for CaseMarker {
+    fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
+        let chars = normalized.get().chars().collect::<Vec<_>>();
+        let mut new_chars: Vec<(char, isize)> = Vec::with_capacity(chars.len());
+        let mut i = 0;
+        while i < chars.len() {
+            // Find the current word
+            let mut end = i;
+            while end < chars.len() && chars[end].is_alphabetic() {
+                end += 1;
+            }
+            if end == i {
+                new_chars.push((chars[i], 0));
+                i += 1;
+                continue;
+            }
+
+            let word = &chars[i..end];
+            let lowercased = word
+                .iter()
+                .map(|c| reversible_lowercase(*c))
+                .collect::<Option<Vec<_>>>();
+            match lowercased {
+                Some(lowercased) if word.len() > 1 => {
+                    push_marked(&mut new_chars, &self.all_caps, lowercased[0]);
+                    new_chars.extend(lowercased[1..].iter().map(|c| (*c, 0)));
+                }
+                _ => {
+                    for c in word {
+                        match reversible_lowercase(*c) {
+                            Some(lower) => push_marked(&mut new_chars, &self.capitalized, lower),
+                            None => new_chars.push((*c, 0)),
+                        }
+                    }
+                }
+            }
+            i = end;
+        }
+
+        normalized.transform(new_chars.into_iter(), 0);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Range;
+
+    #[test]
+    fn markers() {
+        let mut n = NormalizedString::from("Hello WORLD, iPhone ÉCOLE A İstanbul ß");
+        CaseMarker::default().normalize(&mut n).unwrap();
+        assert_eq!(n.get(), "⇧hello ⇪world, i⇧phone ⇪école ⇧a İstanbul ß");
+
+        // Each marker is aligned with its letter
+        assert_eq!(n.get_range_original(Range::Normalized(0..2)), Some("H"));
+        assert_eq!(n.get_range_original(Range::Normalized(7..9)), Some("W"));
+        assert_eq!(
+            n.get_range_original(Range::Normalized(7..13)),
+            Some("WORLD")
+        );
+        assert_eq!(n.get_range_original(Range::Normalized(16..18)), Some("P"));
+    }
+
+    #[test]
+    fn round_trip() {
+        let marker = CaseMarker::new("<c>".into(), "<C>".into());
+        let decoder = marker.decoder(None);
+        for s in &[
+            "Hello WORLD, iPhone ÉCOLE A İstanbul ß",
+            "McDonald's I/O NASA-approved",
+            "ǅemal ΣΊΣΥΦΟΣ Ωmega 42ABC",
+        ] {
+            let mut n = NormalizedString::from(s);
+            marker.normalize(&mut n).unwrap();
+            assert_eq!(decoder.decode(vec![n.get().to_owned()]).unwrap(), *s);
+        }
+    }
+
+    #[test]
+    fn tokenizer() {
+        use crate::models::wordlevel::WordLevelBuilder;
+        use crate::pre_tokenizers::whitespace::Whitespace;
+        use crate::tokenizer::Tokenizer;
+
+        let vocab = ["[UNK]", "hello", "world"]
+            .iter()
+            .enumerate()
+            .map(|(i, w)| (w.to_string(), i as u32))
+            .collect();
+        let mut tokenizer = Tokenizer::new(Box::new(
+            WordLevelBuilder::new()
+                .vocab(vocab)
+                .unk_token("[UNK]".into())
+                .build(),
+        ));
+        let marker = CaseMarker::default();
+        tokenizer.with_pre_tokenizer(Box::new(Whitespace));
+        tokenizer.with_decoder(Box::new(marker.decoder(None)));
+        tokenizer.add_tokens(&marker.added_tokens());
+        tokenizer.with_normalizer(Box::new(marker));
+
+        let encoding = tokenizer.encode("Hello WORLD", false).unwrap();
+        assert_eq!(encoding.get_tokens(), &["⇧", "hello", "⇪", "world"]);
+        assert_eq!(
+            tokenizer.decode(encoding.get_ids().to_vec(), true).unwrap(),
+            "Hello WORLD"
+        );
+    }
+}
@@ -0,0 +1,74 @@
+use crate::tokenizer::{remove_chars, NormalizedString, Normalizer, Result};
+use serde::{Deserialize, Serialize};
+
+/// Replaces the look-alike characters (like the Cyrillic `а`, the fullwidth letters or the
+/// mathematical alphanumerics) by their prototype, following the skeleton mapping of the
+/// Unicode confusables (cf. http://www.unicode.org/reports/tr39/#Confusable_Detection).
+///
+/// The skeleton of a string is not meant to be displayed: it is only useful to compare strings
+/// that have both been processed with it. For example, `m` becomes `rn` since these are
+/// confusable. This normalizer should thus be used both when training and when tokenizing.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Confusables;
+
+#[typetag::serde]
+impl Normalizer for Confusables {
+    fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
+        // The skeleton is defined on the NFD form, but we use NFKD to also handle all the
+        // compatibility variants (like the fullwidth letters). Once decomposed, each char can be
+        // mapped independently, which lets us keep the alignments.
+        normalized.nfkd();
+
+        let mut new_chars: Vec<(char, isize)> = vec![];
+        let mut initial_offset = 0;
+        normalized.for_each(|c| {
+            let mut buf = [0; 4];
+            let skeleton = unicode_security::skeleton(c.encode_utf8(&mut buf)).collect::<Vec<_>>();
+            if skeleton.is_empty() {
+                // The char is removed
+                remove_chars(&mut new_chars, &mut initial_offset, 1);
+            } else {
+                new_chars.extend(
+                    skeleton
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, c)| (c, if i > 0 { 1 } else { 0 })),
+                );
+            }
+        });
+        normalized.transform(new_chars.into_iter(), initial_offset);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Range;
+
+    fn skeleton(s: &str) -> NormalizedString {
+        let mut n = NormalizedString::from(s);
+        Confusables.normalize(&mut n).unwrap();
+        n
+    }
+
+    #[test]
+    fn look_alikes() {
+        // Cyrillic, fullwidth and mathematical letters
+        let plain = skeleton("paypal");
+        for spoof in &["раураl", "ｐａｙｐａｌ", "𝐩𝐚𝐲𝐩𝐚𝐥"] {
+            assert_eq!(skeleton(spoof).get(), plain.get());
+        }
+    }
+
+    #[test]
+    fn alignments() {
+        let n = skeleton("ｍоdеrаtiоn");
+        assert_eq!(n.get(), skeleton("moderation").get());
+        // `m` becomes `rn`, both aligned with the original char
+        assert_eq!(n.get_range_original(Range::Normalized(0..2)), Some("ｍ"));
+        assert_eq!(n.get_range_original(Range::Normalized(2..3)), Some("о"));
+        assert_eq!(n.convert_offsets(Range::Normalized(3..5)), Some(2..4));
+    }
+}
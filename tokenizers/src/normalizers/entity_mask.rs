@@ -0,0 +1,283 @@
+use crate::tokenizer::{with_gaps, NormalizedString, Normalizer, Offsets, Pattern, Result};
+use onig::Regex;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+
+static EMAIL: &str = r"[\w.%+\-]+@[\w\-]+(?:\.[\w\-]+)*\.[a-zA-Z]{2,}";
+static URL: &str = r#"(?:(?:https?|ftp)://|www\.)[^\s<>"]*[^\s<>".,;:!?'()\[\]{}]"#;
+static NUMBER: &str = r"\d+(?:[.,]\d+)*";
+
+/// A user-defined class of entities
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomEntity {
+    /// The regex matching the entities
+    pub pattern: String,
+    /// The placeholder replacing each entity
+    pub placeholder: String,
+}
+
+struct Config {
+    url: Option<String>,
+    email: Option<String>,
+    number: Option<String>,
+    custom: Vec<CustomEntity>,
+}
+
+/// An `EntityMaskBuilder` can be used to create an `EntityMask` with a custom configuration.
+pub struct EntityMaskBuilder {
+    config: Config,
+}
+
+impl Default for EntityMaskBuilder {
+    fn default() -> Self {
+        Self {
+            config: Config {
+                url: Some("<url>".into()),
+                email: Some("<email>".into()),
+                number: Some("<number>".into()),
+                custom: vec![],
+            },
+        }
+    }
+}
+
+impl EntityMaskBuilder {
+    /// Constructs a new `EntityMaskBuilder`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the placeholder for the URLs, or `None` to keep them.
+    pub fn url(mut self, placeholder: Option<String>) -> Self {
+        self.config.url = placeholder;
+        self
+    }
+
+    /// Set the placeholder for the email addresses, or `None` to keep them.
+    pub fn email(mut self, placeholder: Option<String>) -> Self {
+        self.config.email = placeholder;
+        self
+    }
+
+    /// Set the placeholder for the numbers, or `None` to keep them.
+    pub fn number(mut self, placeholder: Option<String>) -> Self {
+        self.config.number = placeholder;
+        self
+    }
+
+    /// Add a custom class of entities, matched by the given regex.
+    pub fn custom(mut self, pattern: String, placeholder: String) -> Self {
+        self.config.custom.push(CustomEntity {
+            pattern,
+            placeholder,
+        });
+        self
+    }
+
+    /// Returns an `EntityMask` with the configuration, compiling all the regexes.
+    pub fn build(self) -> Result<EntityMask> {
+        let config = self.config;
+
+        // The custom entities have priority, and emails must be masked before the URLs and
+        // numbers they might contain
+        let mut regexes = vec![];
+        for entity in &config.custom {
+            regexes.push((Regex::new(&entity.pattern)?, entity.placeholder.clone()));
+        }
+        for (pattern, placeholder) in &[
+            (EMAIL, &config.email),
+            (URL, &config.url),
+            (NUMBER, &config.number),
+        ] {
+            if let Some(placeholder) = placeholder {
+                regexes.push((Regex::new(pattern)?, placeholder.clone()));
+            }
+        }
+
+        Ok(EntityMask {
+            url: config.url,
+            email: config.email,
+            number: config.number,
+            custom: config.custom,
+            regexes,
+        })
+    }
+}
+
+/// We use this custom deserializer to compile the regexes of `EntityMask`
+#[doc(hidden)]
+#[derive(Deserialize)]
+struct EntityMaskDeserializer {
+    url: Option<String>,
+    email: Option<String>,
+    number: Option<String>,
+    custom: Vec<CustomEntity>,
+}
+
+impl TryFrom<EntityMaskDeserializer> for EntityMask {
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    fn try_from(v: EntityMaskDeserializer) -> Result<Self> {
+        EntityMaskBuilder {
+            config: Config {
+                url: v.url,
+                email: v.email,
+                number: v.number,
+                custom: v.custom,
+            },
+        }
+        .build()
+    }
+}
+
+/// Masks the URLs, email addresses, numbers and any user-defined class of entities, by
+/// replacing each of them with a placeholder (like `<url>`). Each placeholder is aligned with
+/// the whole entity, so the offsets still locate the masked entity in the original string.
+#[derive(Serialize, Deserialize)]
+#[serde(try_from = "EntityMaskDeserializer")]
+pub struct EntityMask {
+    url: Option<String>,
+    email: Option<String>,
+    number: Option<String>,
+    custom: Vec<CustomEntity>,
+    #[serde(skip)]
+    regexes: Vec<(Regex, String)>,
+}
+
+impl Default for EntityMask {
+    fn default() -> Self {
+        EntityMaskBuilder::default().build().unwrap()
+    }
+}
+
+impl EntityMask {
+    /// Initialize a `EntityMaskBuilder`.
+    pub fn builder() -> EntityMaskBuilder {
+        EntityMaskBuilder::new()
+    }
+
+    /// Find all the entities, returning their offsets with the index of the matching regex.
+    /// When multiple entities overlap, we keep the one starting first, or with the highest
+    /// priority if they start at the same position.
+    fn find_entities(&self, s: &str) -> Vec<(Offsets, usize)> {
+        let mut candidates = self
+            .regexes
+            .iter()
+            .enumerate()
+            .flat_map(|(i, (regex, _))| {
+                regex
+                    .find_iter(s)
+                    .filter(|(start, end)| start < end)
+                    .map(move |offsets| (offsets, i))
+            })
+            .collect::<Vec<_>>();
+        candidates.sort_by_key(|((start, _), i)| (*start, *i));
+
+        let mut end = 0;
+        candidates
+            .into_iter()
+            .filter(|((s, e), _)| {
+                if *s >= end {
+                    end = *e;
+                    true
+                } else {
+                    false
+                }
+            })
+            .collect()
+    }
+}
+
+/// The entities found in a string, used as a `Pattern`
+struct Entities(Vec<(Offsets, usize)>);
+
+impl Pattern for &Entities {
+    fn find_matches(&self, inside: &str) -> Result<Vec<(Offsets, bool)>> {
+        Ok(with_gaps(
+            inside,
+            self.0.iter().map(|(offsets, _)| *offsets),
+        ))
+    }
+}
+
+#[typetag::serde]
+impl Normalizer for EntityMask {
+    fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
+        let entities = Entities(self.find_entities(normalized.get()));
+        let mut placeholders = entities.0.iter().map(|(_, i)| self.regexes[*i].1.clone());
+        normalized.replace_with(&entities, |_| placeholders.next())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Range;
+
+    #[test]
+    fn mask() {
+        let original =
+            "Mail john.doe@example.com or see https://example.com/a?b=1, room 42 at 3.14pm";
+        let mut n = NormalizedString::from(original);
+        EntityMask::default().normalize(&mut n).unwrap();
+
+        assert_eq!(
+            n.get(),
+            "Mail <email> or see <url>, room <number> at <number>pm"
+        );
+        assert_eq!(
+            n.get_range_original(Range::Normalized(5..12)),
+            Some("john.doe@example.com")
+        );
+        assert_eq!(
+            n.get_range_original(Range::Normalized(20..25)),
+            Some("https://example.com/a?b=1")
+        );
+        assert_eq!(
+            n.get_range_original(Range::Normalized(44..52)),
+            Some("3.14")
+        );
+    }
+
+    #[test]
+    fn custom() {
+        let mask = EntityMask::builder()
+            .number(None)
+            .custom(r"\b\d{3}-\d{4}\b".into(), "<phone>".into())
+            .build()
+            .unwrap();
+
+        let mut n = NormalizedString::from("Call 555-1234 before 9");
+        mask.normalize(&mut n).unwrap();
+        assert_eq!(n.get(), "Call <phone> before 9");
+        assert_eq!(
+            n.get_range_original(Range::Normalized(5..12)),
+            Some("555-1234")
+        );
+    }
+
+    #[test]
+    fn serialization() {
+        let mask = EntityMask::builder()
+            .url(None)
+            .custom("[A-Z]{3}".into(), "<code>".into())
+            .build()
+            .unwrap();
+        let serialized = serde_json::to_string(&mask).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"url":null,"email":"<email>","number":"<number>","custom":[{"pattern":"[A-Z]{3}","placeholder":"<code>"}]}"#
+        );
+
+        let deserialized: EntityMask = serde_json::from_str(&serialized).unwrap();
+        let mut n = NormalizedString::from("ABC 12 www.example.com");
+        deserialized.normalize(&mut n).unwrap();
+        assert_eq!(n.get(), "<code> <number> www.example.com");
+
+        // Invalid regexes fail at deserialization
+        assert!(serde_json::from_str::<EntityMask>(
+            r#"{"url":null,"email":null,"number":null,"custom":[{"pattern":"(","placeholder":""}]}"#
+        )
+        .is_err());
+    }
+}
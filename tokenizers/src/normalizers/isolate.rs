@@ -0,0 +1,153 @@
+use crate::tokenizer::{NormalizedString, Normalizer, Result};
+use serde::{Deserialize, Serialize};
+
+/// A class of characters, usually a set of Unicode blocks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CharClass {
+    /// The CJK Unified Ideographs, as handled by the original BERT
+    Chinese,
+    /// The Japanese Hiragana
+    Hiragana,
+    /// The Japanese Katakana (including the halfwidth forms)
+    Katakana,
+    /// The Thai script
+    Thai,
+    /// The most common emoji and pictographs
+    Emoji,
+    /// Any range of code points (inclusive)
+    Range(u32, u32),
+}
+
+impl CharClass {
+    /// Whether the given char is part of this class
+    pub fn contains(&self, c: char) -> bool {
+        let c = c as u32;
+        match self {
+            // This defines a "chinese character" as anything in the CJK Unicode block:
+            //   https://en.wikipedia.org/wiki/CJK_Unified_Ideographs_(Unicode_block)
+            //
+            // Note that the CJK Unicode block is NOT all Japanese and Korean characters,
+            // despite its name. The modern Korean Hangul alphabet is a different block,
+            // as is Japanese Hiragana and Katakana. Those alphabets are used to write
+            // space-separated words, so they are not treated specially and handled
+            // like for all of the other languages.
+            CharClass::Chinese => matches!(
+                c,
+                0x4E00..=0x9FFF
+                    | 0x3400..=0x4DBF
+                    | 0x20000..=0x2A6DF
+                    | 0x2A700..=0x2B73F
+                    | 0x2B740..=0x2B81F
+                    | 0x2B920..=0x2CEAF
+                    | 0xF900..=0xFAFF
+                    | 0x2F800..=0x2FA1F
+            ),
+            CharClass::Hiragana => matches!(c, 0x3040..=0x309F),
+            CharClass::Katakana => matches!(c, 0x30A0..=0x30FF | 0x31F0..=0x31FF | 0xFF65..=0xFF9F),
+            CharClass::Thai => matches!(c, 0x0E00..=0x0E7F),
+            CharClass::Emoji => matches!(
+                c,
+                0x2600..=0x27BF | 0x1F300..=0x1F5FF | 0x1F600..=0x1F64F | 0x1F680..=0x1F6FF
+                    | 0x1F900..=0x1F9FF
+                    | 0x1FA70..=0x1FAFF
+            ),
+            CharClass::Range(start, end) => *start <= c && c <= *end,
+        }
+    }
+}
+
+/// Puts spaces around each char for which `isolate` returns true, so they get split
+pub(crate) fn isolate_chars<F: Fn(char) -> bool>(normalized: &mut NormalizedString, isolate: F) {
+    let mut new_chars: Vec<(char, isize)> = vec![];
+    normalized.for_each(|c| {
+        if isolate(c) {
+            new_chars.extend(&[(' ', 1), (c, 0), (' ', 1)]);
+        } else {
+            new_chars.push((c, 0));
+        }
+    });
+    normalized.transform(new_chars.into_iter(), 0);
+}
+
+/// Puts spaces around every char of the given classes, just like `BertNormalizer` does with
+/// the chinese characters.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IsolateChars {
+    classes: Vec<CharClass>,
+}
+
+impl IsolateChars {
+    pub fn new(classes: Vec<CharClass>) -> Self {
+        Self { classes }
+    }
+}
+
+#[typetag::serde]
+impl Normalizer for IsolateChars {
+    fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
+        isolate_chars(normalized, |c| {
+            self.classes.iter().any(|class| class.contains(c))
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Range;
+
+    #[test]
+    fn isolate() {
+        let mut n = NormalizedString::from("日本語のテキストกขค🙂");
+        IsolateChars::new(vec![
+            CharClass::Chinese,
+            CharClass::Katakana,
+            CharClass::Emoji,
+        ])
+        .normalize(&mut n)
+        .unwrap();
+
+        assert_eq!(n.get(), " 日  本  語 の テ  キ  ス  ト กขค 🙂 ");
+        assert_eq!(n.get_range_original(Range::Normalized(9..10)), Some("の"));
+    }
+
+    #[test]
+    fn ranges() {
+        let thai = CharClass::Range(0x0E00, 0x0E7F);
+        assert!(thai.contains('ก'));
+        assert!(!thai.contains('a'));
+        assert_eq!(CharClass::Thai.contains('ก'), thai.contains('ก'));
+    }
+
+    #[test]
+    fn bert_normalizer() {
+        use crate::normalizers::bert::BertNormalizer;
+
+        let bert = BertNormalizer::new(false, true, Some(false), false)
+            .with_isolated_chars(vec![CharClass::Hiragana]);
+        let mut n = NormalizedString::from("日本のa");
+        bert.normalize(&mut n).unwrap();
+        assert_eq!(n.get(), " 日  本  の a");
+
+        // The isolated chars are optional when deserializing
+        let bert: BertNormalizer = serde_json::from_str(
+            r#"{"clean_text":false,"handle_chinese_chars":true,"strip_accents":null,"lowercase":false}"#,
+        )
+        .unwrap();
+        let mut n = NormalizedString::from("日本のa");
+        bert.normalize(&mut n).unwrap();
+        assert_eq!(n.get(), " 日  本 のa");
+    }
+
+    #[test]
+    fn serialization() {
+        let isolate = IsolateChars::new(vec![CharClass::Hiragana, CharClass::Range(1, 2)]);
+        let serialized = serde_json::to_string(&isolate).unwrap();
+        assert_eq!(serialized, r#"{"classes":["Hiragana",{"Range":[1,2]}]}"#);
+        assert_eq!(
+            serde_json::from_str::<IsolateChars>(&serialized).unwrap(),
+            isolate
+        );
+    }
+}
@@ -0,0 +1,315 @@
+use crate::tokenizer::{with_gaps, NormalizedString, Normalizer, Offsets, Pattern, Result};
+use onig::Regex;
+use serde::{Deserialize, Serialize};
+
+lazy_static! {
+    static ref SCRIPTS: Regex = Regex::new(r"(?im)<(script|style)\b[^>]*>.*?</\1\s*>").unwrap();
+    static ref COMMENTS: Regex = Regex::new(r"(?m)<!--.*?-->").unwrap();
+    static ref TAGS: Regex = Regex::new(r"<(?:/?[a-zA-Z][^>]*|![^>]*|\?[^>]*)>").unwrap();
+    static ref ENTITIES: Regex = Regex::new(r"&(?:#[0-9]+|#[xX][0-9a-fA-F]+|[a-zA-Z]+);").unwrap();
+    static ref FENCE: Regex = Regex::new(r"^[ ]{0,3}(?:```|~~~)").unwrap();
+    // Horizontal rules and the underlines of the setext headings
+    static ref RULE: Regex =
+        Regex::new(r"^[ ]{0,3}(?:(?:[-*_][ \t]*){3,}|=+[ \t]*|-+[ \t]*)$").unwrap();
+    static ref HEADING_CLOSE: Regex = Regex::new(r"(?:^|[ \t]+)#+[ \t]*$").unwrap();
+    static ref REFERENCE: Regex = Regex::new(r"^[ ]{0,3}\[[^\]]+\]:[ \t]*\S").unwrap();
+    // The blockquotes, followed by a heading or a list item
+    static ref BLOCK_PREFIX: Regex = Regex::new(
+        r"^(?:[ ]{0,3}>[ ]?)*(?:[ \t]*[*+-][ \t]+|[ ]{0,3}#{1,6}(?:[ \t]+|$))?"
+    )
+    .unwrap();
+    // Each alternative captures the text that we keep
+    static ref INLINE: Regex = Regex::new(concat!(
+        // Escaped chars, code spans and autolinks
+        r"\\([\\`*_{}\[\]()#+\-.!<>~|])|``(.+?)``|`([^`]+)`|<((?:https?|ftp|mailto):[^>\s]+)>",
+        // Images and links
+        r"|!\[([^\]]*)\]\([^)]*\)|\[([^\]]+)\]\([^)]*\)|\[([^\]]+)\]\[[^\]]*\]",
+        // Emphasis and strikethrough
+        r"|\*\*(?=\S)(.+?)(?<=\S)\*\*|__(?=\S)(.+?)(?<=\S)__|~~(?=\S)(.+?)(?<=\S)~~",
+        r"|\*(?=[^\s*])(.+?)(?<=[^\s*])\*|(?<!\w)_(?=[^\s_])(.+?)(?<=[^\s_])_(?!\w)",
+    ))
+    .unwrap();
+}
+
+/// The number of groups of `INLINE` whose content is kept as is (escaped chars, code spans and
+/// autolinks). The content of the other groups can contain some more inline syntax.
+const VERBATIM_GROUPS: usize = 4;
+
+/// Find the inline syntax in `s` (starting at `offset` in the whole string)
+fn inline_syntax(s: &str, offset: usize, syntax: &mut Vec<Offsets>) {
+    for captures in INLINE.captures_iter(s) {
+        let (start, end) = captures.pos(0).unwrap();
+        let group = (1..captures.len()).find(|i| captures.pos(*i).is_some());
+        match group.and_then(|i| captures.pos(i).map(|pos| (i, pos))) {
+            Some((i, (text_start, text_end))) => {
+                syntax.push((offset + start, offset + text_start));
+                if i > VERBATIM_GROUPS {
+                    inline_syntax(&s[text_start..text_end], offset + text_start, syntax);
+                }
+                syntax.push((offset + text_end, offset + end));
+            }
+            None => syntax.push((offset + start, offset + end)),
+        }
+    }
+}
+
+/// The Markdown syntax found in a string, used as a `Pattern`
+struct Markdown;
+
+impl Pattern for &Markdown {
+    fn find_matches(&self, inside: &str) -> Result<Vec<(Offsets, bool)>> {
+        let mut syntax = vec![];
+        let mut in_fence = false;
+        let mut start = 0;
+        while start < inside.len() {
+            let end = inside[start..]
+                .find('\n')
+                .map_or(inside.len(), |i| start + i + 1);
+            let line = inside[start..end].trim_end_matches(&['\n', '\r'][..]);
+
+            if FENCE.find(line).is_some() {
+                // The fences are removed, but the code inside is kept as is
+                in_fence = !in_fence;
+                syntax.push((start, end));
+            } else if !in_fence {
+                if RULE.find(line).is_some() || REFERENCE.find(line).is_some() {
+                    syntax.push((start, end));
+                } else {
+                    let prefix = BLOCK_PREFIX.find(line).map_or(0, |(_, e)| e);
+                    let mut text = &line[prefix..];
+                    syntax.push((start, start + prefix));
+                    // The headings can also be closed by some `#`
+                    let suffix = if line[..prefix].trim_end().ends_with('#') {
+                        HEADING_CLOSE.find(text)
+                    } else {
+                        None
+                    };
+                    if let Some((s, _)) = suffix {
+                        text = &text[..s];
+                    }
+                    inline_syntax(text, start + prefix, &mut syntax);
+                    if let Some((s, e)) = suffix {
+                        syntax.push((start + prefix + s, start + prefix + e));
+                    }
+                }
+            }
+
+            start = end;
+        }
+
+        Ok(with_gaps(inside, syntax.into_iter()))
+    }
+}
+
+/// Decode the given entity (including the leading `&` and the trailing `;`), returning `None`
+/// if it is unknown
+fn decode_entity(entity: &str) -> Option<String> {
+    let name = &entity[1..entity.len() - 1];
+    let c = if let Some(hex) = name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+        std::char::from_u32(u32::from_str_radix(hex, 16).ok()?)?
+    } else if let Some(dec) = name.strip_prefix('#') {
+        std::char::from_u32(dec.parse().ok()?)?
+    } else {
+        match name {
+            "amp" => '&',
+            "lt" => '<',
+            "gt" => '>',
+            "quot" => '"',
+            "apos" => '\'',
+            "nbsp" => '\u{a0}',
+            "copy" => '©',
+            "reg" => '®',
+            "trade" => '™',
+            "hellip" => '…',
+            "ndash" => '–',
+            "mdash" => '—',
+            "lsquo" => '‘',
+            "rsquo" => '’',
+            "ldquo" => '“',
+            "rdquo" => '”',
+            "laquo" => '«',
+            "raquo" => '»',
+            "bull" => '•',
+            "middot" => '·',
+            "deg" => '°',
+            "times" => '×',
+            "divide" => '÷',
+            "sect" => '§',
+            "para" => '¶',
+            "cent" => '¢',
+            "pound" => '£',
+            "yen" => '¥',
+            "euro" => '€',
+            _ => return None,
+        }
+    };
+
+    Some(c.to_string())
+}
+
+/// Strips the markup (HTML, XML, and optionally Markdown) to keep only the text:
+///   1. Optionally removes the `<script>` and `<style>` elements with their content
+///   2. Removes the comments
+///   3. Optionally removes the Markdown syntax: the headings, blockquotes and list markers,
+///      the horizontal rules, the code fences and backticks, the emphasis, and the links and
+///      images (keeping their text)
+///   4. Removes all the tags
+///   5. Decodes the entities (`&amp;`, `&#x2014;`, ...)
+///
+/// Every character that is kept stays aligned with its position in the markup, and each
+/// decoded entity is aligned with the whole entity.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StripMarkup {
+    /// Whether to remove the content of the `<script>` and `<style>` elements
+    strip_scripts: bool,
+    /// Whether to remove the Markdown syntax
+    strip_markdown: bool,
+}
+
+impl Default for StripMarkup {
+    fn default() -> Self {
+        Self {
+            strip_scripts: true,
+            strip_markdown: false,
+        }
+    }
+}
+
+impl StripMarkup {
+    pub fn new(strip_scripts: bool, strip_markdown: bool) -> Self {
+        Self {
+            strip_scripts,
+            strip_markdown,
+        }
+    }
+}
+
+#[typetag::serde]
+impl Normalizer for StripMarkup {
+    fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
+        if self.strip_scripts {
+            normalized.replace(&*SCRIPTS, "")?;
+        }
+        normalized.replace(&*COMMENTS, "")?;
+        // The autolinks (`<https://...>`) look like tags, so the Markdown goes first
+        if self.strip_markdown {
+            normalized.replace(&Markdown, "")?;
+        }
+        normalized.replace(&*TAGS, "")?;
+        normalized.replace_with(&*ENTITIES, decode_entity)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Range;
+
+    #[test]
+    fn strip_tags() {
+        let original = r#"<p class="intro">Hello <b>world</b>!</p><!-- <b>comment</b> -->"#;
+        let mut n = NormalizedString::from(original);
+        StripMarkup::default().normalize(&mut n).unwrap();
+
+        assert_eq!(n.get(), "Hello world!");
+        assert_eq!(n.convert_offsets(Range::Normalized(0..5)), Some(17..22));
+        assert_eq!(n.convert_offsets(Range::Normalized(6..11)), Some(26..31));
+        assert_eq!(
+            n.get_range_original(Range::Normalized(6..12)),
+            Some("world</b>!")
+        );
+    }
+
+    #[test]
+    fn entities() {
+        let original = "Fish &amp; chips &#x2014; &#169; &unknown; 1 &lt; 2";
+        let mut n = NormalizedString::from(original);
+        StripMarkup::default().normalize(&mut n).unwrap();
+
+        assert_eq!(n.get(), "Fish & chips — © &unknown; 1 < 2");
+        assert_eq!(n.get_range_original(Range::Normalized(5..6)), Some("&amp;"));
+        assert_eq!(
+            n.get_range_original(Range::Normalized(13..14)),
+            Some("&#x2014;")
+        );
+        assert_eq!(
+            n.get_range_original(Range::Normalized(17..26)),
+            Some("&unknown;")
+        );
+    }
+
+    #[test]
+    fn scripts() {
+        let original = "<style>p { color: red; }</style><SCRIPT type=\"text/javascript\">\nif (a < b) {}\n</SCRIPT>Text";
+
+        let mut n = NormalizedString::from(original);
+        StripMarkup::new(true, false).normalize(&mut n).unwrap();
+        assert_eq!(n.get(), "Text");
+        assert_eq!(n.get_range_original(Range::Normalized(0..4)), Some("Text"));
+
+        let mut n = NormalizedString::from(original);
+        StripMarkup::new(false, false).normalize(&mut n).unwrap();
+        assert_eq!(n.get(), "p { color: red; }\nif (a < b) {}\nText");
+    }
+
+    #[test]
+    fn markdown() {
+        let original = "# Title\n\nSome **bold _and_ [linked](http://a.b)** text, `a*b*c` and\\_snake_case_.\n\n> - first\n> - ![alt](img.png)\n\n---\n```rust\nlet x = *y;\n```\nSee <https://example.com>\n";
+        let mut n = NormalizedString::from(original);
+        StripMarkup::new(true, true).normalize(&mut n).unwrap();
+
+        assert_eq!(
+            n.get(),
+            "Title\n\nSome bold and linked text, a*b*c and_snake_case_.\n\nfirst\nalt\n\nlet x = *y;\nSee https://example.com\n"
+        );
+        assert_eq!(n.get_range_original(Range::Normalized(0..5)), Some("Title"));
+        assert_eq!(n.get_range_original(Range::Normalized(17..20)), Some("and"));
+        assert_eq!(
+            n.get_range_original(Range::Normalized(21..27)),
+            Some("linked")
+        );
+        assert_eq!(
+            n.get_range_original(Range::Normalized(34..39)),
+            Some("a*b*c")
+        );
+        assert_eq!(
+            n.get_range_original(Range::Normalized(58..63)),
+            Some("first")
+        );
+        assert_eq!(n.get_range_original(Range::Normalized(64..67)), Some("alt"));
+        assert_eq!(
+            n.get_range_original(Range::Normalized(85..104)),
+            Some("https://example.com")
+        );
+
+        // Without the option, only the HTML is stripped
+        let mut n = NormalizedString::from("**bold** <b>tag</b>");
+        StripMarkup::default().normalize(&mut n).unwrap();
+        assert_eq!(n.get(), "**bold** tag");
+    }
+
+    #[test]
+    fn markdown_with_html() {
+        let original =
+            "## Fish &amp; <em>chips</em> ##\n* [Menu](/menu \"The menu\") &mdash; __now__";
+        let mut n = NormalizedString::from(original);
+        StripMarkup::new(true, true).normalize(&mut n).unwrap();
+
+        assert_eq!(n.get(), "Fish & chips\nMenu — now");
+        assert_eq!(n.get_range_original(Range::Normalized(5..6)), Some("&amp;"));
+        assert_eq!(
+            n.get_range_original(Range::Normalized(7..12)),
+            Some("chips")
+        );
+        assert_eq!(
+            n.get_range_original(Range::Normalized(13..17)),
+            Some("Menu")
+        );
+        assert_eq!(
+            n.get_range_original(Range::Normalized(18..19)),
+            Some("&mdash;")
+        );
+    }
+}
@@ -1,4 +1,14 @@
 pub mod bert;
+pub mod case_fold;
+pub mod case_marker;
+pub mod confusables;
+pub mod entity_mask;
+pub mod isolate;
+pub mod markup;
+pub mod nmt;
+pub mod precompiled;
+pub mod replace;
 pub mod strip;
+pub mod transliterate;
 pub mod unicode;
 pub mod utils;
@@ -0,0 +1,205 @@
+use crate::tokenizer::{remove_chars, NormalizedString, Normalizer, Result};
+use onig::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use unicode_categories::UnicodeCategories;
+
+/// The default replacements: the various Unicode spaces become a classic space, while the
+/// zero-width characters are removed.
+fn default_replacements() -> BTreeMap<char, String> {
+    let spaces = [
+        '\u{00a0}', '\u{1680}', '\u{2000}', '\u{2001}', '\u{2002}', '\u{2003}', '\u{2004}',
+        '\u{2005}', '\u{2006}', '\u{2007}', '\u{2008}', '\u{2009}', '\u{200a}', '\u{2028}',
+        '\u{2029}', '\u{202f}', '\u{205f}', '\u{3000}',
+    ];
+    let zero_width = ['\u{200b}', '\u{200c}', '\u{200d}', '\u{2060}', '\u{feff}'];
+
+    spaces
+        .iter()
+        .map(|c| (*c, " ".to_owned()))
+        .chain(zero_width.iter().map(|c| (*c, "".to_owned())))
+        .collect()
+}
+
+/// Checks whether a character is one of the control (Cc) or format (Cf) characters removed by
+/// `Nmt`. This is narrower than the `is_control` of `BertNormalizer`, which removes any
+/// character of the Other category.
+fn is_nmt_control(c: char) -> bool {
+    // These are technically control characters but we count them as whitespace
+    if c == '\t' || c == '\n' || c == '\r' {
+        false
+    } else {
+        c.is_other_control() || c.is_other_format()
+    }
+}
+
+/// Cleans up the text, like it is usually done before Neural Machine Translation:
+///   1. Replaces some specific characters (by default the many Unicode spaces become ` `, and
+///      the zero-width characters are removed)
+///   2. Removes any other control/format character
+///   3. Optionally collapses any run of whitespace into a single ` `
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Nmt {
+    /// Whether to remove the control and format characters (except `\t`, `\n` and `\r`)
+    remove_control: bool,
+    /// The replacement of some specific characters. This has priority over the removal
+    /// of the control characters.
+    replacements: BTreeMap<char, String>,
+    /// Whether to collapse any run of whitespace into a single space
+    collapse_whitespace: bool,
+}
+
+impl Default for Nmt {
+    fn default() -> Self {
+        Self {
+            remove_control: true,
+            replacements: default_replacements(),
+            collapse_whitespace: false,
+        }
+    }
+}
+
+impl Nmt {
+    pub fn new(
+        remove_control: bool,
+        replacements: BTreeMap<char, String>,
+        collapse_whitespace: bool,
+    ) -> Self {
+        Self {
+            remove_control,
+            replacements,
+            collapse_whitespace,
+        }
+    }
+
+    fn do_replace(&self, normalized: &mut NormalizedString) {
+        let mut new_chars: Vec<(char, isize)> = vec![];
+        let mut initial_offset = 0;
+        normalized.for_each(|c| {
+            let replacement = match self.replacements.get(&c) {
+                Some(r) => r.chars().collect(),
+                None if self.remove_control && is_nmt_control(c) => vec![],
+                None => vec![c],
+            };
+
+            if replacement.is_empty() {
+                // The char is removed
+                remove_chars(&mut new_chars, &mut initial_offset, 1);
+            } else {
+                new_chars.extend(
+                    replacement
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, c)| (c, if i > 0 { 1 } else { 0 })),
+                );
+            }
+        });
+        normalized.transform(new_chars.into_iter(), initial_offset);
+    }
+
+    fn do_collapse_whitespace(&self, normalized: &mut NormalizedString) -> Result<()> {
+        lazy_static! {
+            static ref RE: Regex = Regex::new(r"\s+").unwrap();
+        }
+        normalized.replace(&*RE, " ")
+    }
+}
+
+#[typetag::serde]
+impl Normalizer for Nmt {
+    fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
+        self.do_replace(normalized);
+        if self.collapse_whitespace {
+            self.do_collapse_whitespace(normalized)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Range;
+
+    #[test]
+    fn default() {
+        let original = "\u{200b}Hello\u{00a0}\u{0007}world\u{3000}\u{feff}!";
+        let mut n = NormalizedString::from(original);
+        Nmt::default().normalize(&mut n).unwrap();
+
+        assert_eq!(n.get(), "Hello world !");
+        assert_eq!(n.get_range_original(Range::Normalized(0..5)), Some("Hello"));
+        assert_eq!(
+            n.get_range_original(Range::Normalized(6..11)),
+            Some("world")
+        );
+        assert_eq!(n.get_range_original(Range::Normalized(12..13)), Some("!"));
+        assert_eq!(n.convert_offsets(Range::Original(8..13)), Some(6..11));
+    }
+
+    #[test]
+    fn custom_replacements() {
+        let mut replacements = BTreeMap::new();
+        replacements.insert('\u{200d}', "<zwj>".to_owned());
+        let mut n = NormalizedString::from("a\u{200d}b\u{00ad}c");
+        Nmt::new(true, replacements, false)
+            .normalize(&mut n)
+            .unwrap();
+
+        // The soft hyphen is a format character
+        assert_eq!(n.get(), "a<zwj>bc");
+        assert_eq!(
+            n.get_range_original(Range::Normalized(1..6)),
+            Some("\u{200d}")
+        );
+        assert_eq!(n.get_range_original(Range::Normalized(7..8)), Some("c"));
+    }
+
+    #[test]
+    fn expansion_followed_by_removed_char() {
+        let mut replacements = BTreeMap::new();
+        replacements.insert('…', "...".to_owned());
+        let mut n = NormalizedString::from("a…\u{7}b");
+        Nmt::new(true, replacements, false)
+            .normalize(&mut n)
+            .unwrap();
+
+        assert_eq!(n.get(), "a...b");
+        assert_eq!(n.get_range_original(Range::Normalized(3..4)), Some("…"));
+        assert_eq!(n.get_range_original(Range::Normalized(1..4)), Some("…"));
+        assert_eq!(n.get_range_original(Range::Normalized(4..5)), Some("b"));
+    }
+
+    #[test]
+    fn collapse_whitespace() {
+        let mut n = NormalizedString::from("Hello \u{00a0}\t world");
+        Nmt::new(true, default_replacements(), true)
+            .normalize(&mut n)
+            .unwrap();
+
+        assert_eq!(n.get(), "Hello world");
+        assert_eq!(
+            n.get_range_original(Range::Normalized(5..6)),
+            Some(" \u{00a0}\t ")
+        );
+        assert_eq!(
+            n.get_range_original(Range::Normalized(6..11)),
+            Some("world")
+        );
+    }
+
+    #[test]
+    fn serialization() {
+        let mut replacements = BTreeMap::new();
+        replacements.insert('\u{3000}', " ".to_owned());
+        let nmt = Nmt::new(true, replacements, true);
+
+        let serialized = serde_json::to_string(&nmt).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"remove_control":true,"replacements":{"　":" "},"collapse_whitespace":true}"#
+        );
+        assert_eq!(serde_json::from_str::<Nmt>(&serialized).unwrap(), nmt);
+    }
+}
@@ -0,0 +1,377 @@
+use crate::tokenizer::{remove_chars, NormalizedString, Normalizer, Result};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::convert::TryFrom;
+
+#[derive(Debug)]
+pub enum PrecompiledError {
+    /// The charsmap is too short to contain the size of the trie
+    MissingTrieSize,
+    /// The size of the trie is not consistent with the charsmap
+    InvalidTrieSize(usize),
+    /// The trie points to something outside of the normalized strings
+    InvalidOffset(usize),
+    /// One of the normalized strings is not valid UTF-8
+    InvalidString(usize),
+}
+
+impl std::fmt::Display for PrecompiledError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PrecompiledError::MissingTrieSize => {
+                write!(fmt, "Precompiled charsmap is missing the trie size")
+            }
+            PrecompiledError::InvalidTrieSize(size) => {
+                write!(fmt, "Invalid trie size {} in precompiled charsmap", size)
+            }
+            PrecompiledError::InvalidOffset(offset) => {
+                write!(fmt, "Invalid offset {} in precompiled charsmap", offset)
+            }
+            PrecompiledError::InvalidString(offset) => write!(
+                fmt,
+                "Invalid UTF-8 string at offset {} in precompiled charsmap",
+                offset
+            ),
+        }
+    }
+}
+impl std::error::Error for PrecompiledError {}
+
+/// A Darts-clone double array trie, as used by SentencePiece.
+/// cf. https://github.com/s-yata/darts-clone
+struct DoubleArray {
+    array: Vec<u32>,
+}
+
+impl DoubleArray {
+    fn has_leaf(unit: u32) -> bool {
+        (unit >> 8) & 1 == 1
+    }
+
+    fn value(unit: u32) -> usize {
+        (unit & ((1 << 31) - 1)) as usize
+    }
+
+    fn label(unit: u32) -> u32 {
+        unit & ((1 << 31) | 0xFF)
+    }
+
+    fn offset(unit: u32) -> usize {
+        ((unit >> 10) << ((unit & (1 << 9)) >> 6)) as usize
+    }
+
+    /// Find the longest key that is a prefix of `key`, returning the associated value and the
+    /// length of the match in bytes.
+    fn longest_prefix(&self, key: &[u8]) -> Option<(usize, usize)> {
+        let mut node_pos = 0;
+        let mut unit = *self.array.get(node_pos)?;
+        node_pos ^= Self::offset(unit);
+
+        let mut longest = None;
+        for (i, c) in key.iter().enumerate() {
+            if *c == 0 {
+                break;
+            }
+            node_pos ^= *c as usize;
+            unit = match self.array.get(node_pos) {
+                Some(u) => *u,
+                None => break,
+            };
+            if Self::label(unit) != *c as u32 {
+                break;
+            }
+            node_pos ^= Self::offset(unit);
+            if Self::has_leaf(unit) {
+                if let Some(leaf) = self.array.get(node_pos) {
+                    longest = Some((Self::value(*leaf), i + 1));
+                }
+            }
+        }
+
+        longest
+    }
+}
+
+/// This normalizer applies the precompiled charsmap found in the SentencePiece models (like the
+/// `nmt_nfkc` rules), to reproduce the SentencePiece normalization exactly.
+///
+/// The charsmap contains a double array trie, mapping some strings to their normalized version,
+/// and the normalized strings themselves. At each position, the longest match in the trie is
+/// replaced by its normalized version.
+#[derive(Serialize, Deserialize)]
+#[serde(try_from = "PrecompiledDeserializer")]
+pub struct Precompiled {
+    #[serde(serialize_with = "to_base64")]
+    precompiled_charsmap: Vec<u8>,
+    #[serde(skip)]
+    normalized: Vec<u8>,
+    #[serde(skip)]
+    trie: DoubleArray,
+}
+
+impl std::fmt::Debug for Precompiled {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.debug_struct("Precompiled")
+            .field("precompiled_charsmap", &self.precompiled_charsmap.len())
+            .finish()
+    }
+}
+
+fn to_base64<S: Serializer>(bytes: &[u8], serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(&base64::encode(bytes))
+}
+
+fn from_base64<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Vec<u8>, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    base64::decode(&s).map_err(serde::de::Error::custom)
+}
+
+/// We use this custom deserializer to build the trie for `Precompiled`
+#[doc(hidden)]
+#[derive(Deserialize)]
+struct PrecompiledDeserializer {
+    #[serde(deserialize_with = "from_base64")]
+    precompiled_charsmap: Vec<u8>,
+}
+
+impl TryFrom<PrecompiledDeserializer> for Precompiled {
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    fn try_from(v: PrecompiledDeserializer) -> Result<Self> {
+        Self::from(&v.precompiled_charsmap)
+    }
+}
+
+impl Precompiled {
+    /// Create a new `Precompiled` normalizer from the raw bytes of the charsmap, as found in
+    /// the `normalizer_spec.precompiled_charsmap` field of a SentencePiece model.
+    pub fn from(precompiled_charsmap: &[u8]) -> Result<Self> {
+        if precompiled_charsmap.len() < 4 {
+            return Err(Box::new(PrecompiledError::MissingTrieSize));
+        }
+        let mut size = [0u8; 4];
+        size.copy_from_slice(&precompiled_charsmap[..4]);
+        let trie_size = u32::from_le_bytes(size) as usize;
+        // The trie is an array of u32
+        let units = precompiled_charsmap
+            .get(4..4 + trie_size)
+            .map(|trie| trie.chunks_exact(4))
+            .filter(|units| units.remainder().is_empty())
+            .ok_or(PrecompiledError::InvalidTrieSize(trie_size))?;
+
+        let array = units
+            .map(|unit| {
+                let mut bytes = [0u8; 4];
+                bytes.copy_from_slice(unit);
+                u32::from_le_bytes(bytes)
+            })
+            .collect();
+
+        Ok(Self {
+            precompiled_charsmap: precompiled_charsmap.to_vec(),
+            normalized: precompiled_charsmap[4 + trie_size..].to_vec(),
+            trie: DoubleArray { array },
+        })
+    }
+
+    /// Return the normalized string found at the given offset
+    fn normalized_at(&self, offset: usize) -> Result<&str> {
+        let bytes = self
+            .normalized
+            .get(offset..)
+            .ok_or(PrecompiledError::InvalidOffset(offset))?;
+        let len = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+        Ok(std::str::from_utf8(&bytes[..len])
+            .map_err(|_| PrecompiledError::InvalidString(offset))?)
+    }
+}
+
+#[typetag::serde]
+impl Normalizer for Precompiled {
+    fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
+        let original = normalized.get().to_owned();
+
+        let mut transformations: Vec<(char, isize)> = Vec::with_capacity(original.len());
+        let mut initial_offset = 0;
+        let mut pos = 0;
+        while pos < original.len() {
+            let rest = &original[pos..];
+            let (old, new) = match self.trie.longest_prefix(rest.as_bytes()) {
+                Some((offset, len)) => (&rest[..len], self.normalized_at(offset)?),
+                None => {
+                    // No rule for this char, we keep it as is
+                    let len = rest.chars().next().map_or(1, |c| c.len_utf8());
+                    (&rest[..len], &rest[..len])
+                }
+            };
+            pos += old.len();
+
+            let n_old = old.chars().count();
+            let n_new = new.chars().count();
+            if n_new == 0 {
+                // Everything has been removed
+                remove_chars(&mut transformations, &mut initial_offset, n_old);
+            } else {
+                transformations.extend(new.chars().enumerate().map(|(i, c)| {
+                    if i >= n_old {
+                        (c, 1)
+                    } else if i == n_new - 1 {
+                        (c, -((n_old - n_new) as isize))
+                    } else {
+                        (c, 0)
+                    }
+                }));
+            }
+        }
+
+        normalized.transform(transformations.into_iter(), initial_offset);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Range;
+    use std::collections::{BTreeMap, HashSet};
+
+    /// Build a precompiled charsmap, with a (naive) Darts-clone double array builder
+    fn build(rules: &[(&str, &str)]) -> Vec<u8> {
+        let mut normalized = vec![];
+        let mut keys = BTreeMap::new();
+        for (key, value) in rules {
+            keys.insert(key.as_bytes().to_vec(), normalized.len() as u32);
+            normalized.extend(value.as_bytes());
+            normalized.push(0);
+        }
+
+        fn place(
+            prefix: &[u8],
+            node: usize,
+            keys: &BTreeMap<Vec<u8>, u32>,
+            units: &mut Vec<u32>,
+            bases: &mut HashSet<usize>,
+        ) {
+            let mut labels = vec![];
+            if !prefix.is_empty() && keys.contains_key(prefix) {
+                labels.push(0u8);
+            }
+            for key in keys.keys() {
+                if key.len() > prefix.len() && key.starts_with(prefix) {
+                    let label = key[prefix.len()];
+                    if !labels.contains(&label) {
+                        labels.push(label);
+                    }
+                }
+            }
+
+            // Find an unused base where all the children fit, keeping 0 for the root
+            let base = (1..)
+                .find(|base: &usize| {
+                    !bases.contains(base)
+                        && labels
+                            .iter()
+                            .map(|l| base ^ *l as usize)
+                            .all(|i| i != 0 && units.get(i).cloned().unwrap_or(0) == 0)
+                })
+                .unwrap();
+            bases.insert(base);
+            let max = labels.iter().map(|l| base ^ *l as usize).max().unwrap();
+            if units.len() <= max {
+                units.resize(max + 1, 0);
+            }
+
+            units[node] |= ((node ^ base) as u32) << 10;
+            for label in &labels {
+                let child = base ^ *label as usize;
+                if *label == 0 {
+                    units[child] = keys[prefix] | (1 << 31);
+                    units[node] |= 1 << 8;
+                } else {
+                    units[child] = *label as u32;
+                }
+            }
+            for label in labels.into_iter().filter(|l| *l != 0) {
+                let mut prefix = prefix.to_vec();
+                prefix.push(label);
+                place(&prefix, base ^ label as usize, keys, units, bases);
+            }
+        }
+
+        let mut units = vec![0u32];
+        place(&[], 0, &keys, &mut units, &mut HashSet::new());
+
+        let mut charsmap = ((units.len() * 4) as u32).to_le_bytes().to_vec();
+        for unit in units {
+            charsmap.extend(&unit.to_le_bytes());
+        }
+        charsmap.extend(normalized);
+        charsmap
+    }
+
+    fn precompiled() -> Precompiled {
+        Precompiled::from(&build(&[
+            ("Ａ", "A"),
+            ("ﬁ", "fi"),
+            ("\u{200b}", ""),
+            ("ｶ", "カ"),
+            ("ｶﾞ", "ガ"),
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn longest_match() {
+        let mut n = NormalizedString::from("ｶﾞｶ");
+        precompiled().normalize(&mut n).unwrap();
+        assert_eq!(n.get(), "ガカ");
+        assert_eq!(n.convert_offsets(Range::Original(0..2)), Some(0..1));
+        assert_eq!(n.convert_offsets(Range::Original(2..3)), Some(1..2));
+    }
+
+    #[test]
+    fn alignments() {
+        let mut n = NormalizedString::from("\u{200b}ﬁＡ\u{200b}b");
+        precompiled().normalize(&mut n).unwrap();
+        assert_eq!(n.get(), "fiAb");
+        assert_eq!(n.get_range_original(Range::Normalized(0..2)), Some("ﬁ"));
+        assert_eq!(n.get_range_original(Range::Normalized(2..3)), Some("Ａ"));
+        assert_eq!(n.get_range_original(Range::Normalized(3..4)), Some("b"));
+        assert_eq!(n.convert_offsets(Range::Original(2..3)), Some(2..3));
+
+        // An expansion followed by a removed char
+        let mut n = NormalizedString::from("ﬁ\u{200b}b");
+        precompiled().normalize(&mut n).unwrap();
+        assert_eq!(n.get(), "fib");
+        assert_eq!(n.get_range_original(Range::Normalized(1..2)), Some("ﬁ"));
+        assert_eq!(n.get_range_original(Range::Normalized(2..3)), Some("b"));
+    }
+
+    #[test]
+    fn invalid_charsmap() {
+        assert!(Precompiled::from(&[1, 0]).is_err());
+        assert!(Precompiled::from(&[8, 0, 0, 0, 1]).is_err());
+    }
+
+    #[test]
+    fn serialization() {
+        let charsmap = build(&[("Ａ", "A")]);
+        let precompiled = Precompiled::from(&charsmap).unwrap();
+
+        let serialized = serde_json::to_string(&precompiled).unwrap();
+        assert_eq!(
+            serialized,
+            format!(
+                r#"{{"precompiled_charsmap":"{}"}}"#,
+                base64::encode(&charsmap)
+            )
+        );
+        let deserialized: Precompiled = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized.precompiled_charsmap, charsmap);
+
+        let mut n = NormalizedString::from("ＡＡ");
+        deserialized.normalize(&mut n).unwrap();
+        assert_eq!(n.get(), "AA");
+    }
+}
@@ -0,0 +1,154 @@
+use crate::tokenizer::{NormalizedString, Normalizer, Result};
+use onig::Regex;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+
+/// Represents the different patterns that `Replace` can use
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReplacePattern {
+    String(String),
+    Regex(String),
+}
+
+impl From<String> for ReplacePattern {
+    fn from(v: String) -> Self {
+        ReplacePattern::String(v)
+    }
+}
+
+impl From<&str> for ReplacePattern {
+    fn from(v: &str) -> Self {
+        ReplacePattern::String(v.to_owned())
+    }
+}
+
+/// We use this custom deserializer to provide the value for `regex` for `Replace`
+#[doc(hidden)]
+#[derive(Deserialize)]
+struct ReplaceDeserializer {
+    pattern: ReplacePattern,
+    content: String,
+}
+
+impl TryFrom<ReplaceDeserializer> for Replace {
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    fn try_from(v: ReplaceDeserializer) -> Result<Self> {
+        Self::new(v.pattern, v.content)
+    }
+}
+
+/// Replaces anything matching the pattern with the given content. The content is aligned
+/// with the whole match, so the offsets are still mapped to the original string.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(try_from = "ReplaceDeserializer")]
+pub struct Replace {
+    pattern: ReplacePattern,
+    content: String,
+    #[serde(skip)]
+    regex: Regex,
+}
+
+impl Clone for Replace {
+    fn clone(&self) -> Self {
+        Self::new(self.pattern.clone(), &self.content).unwrap()
+    }
+}
+
+impl PartialEq for Replace {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern && self.content == other.content
+    }
+}
+
+impl Replace {
+    pub fn new<I: Into<ReplacePattern>, C: Into<String>>(pattern: I, content: C) -> Result<Self> {
+        let pattern: ReplacePattern = pattern.into();
+        let regex = match &pattern {
+            ReplacePattern::String(s) => Regex::new(&regex::escape(s))?,
+            ReplacePattern::Regex(r) => Regex::new(r)?,
+        };
+
+        Ok(Self {
+            pattern,
+            content: content.into(),
+            regex,
+        })
+    }
+}
+
+#[typetag::serde]
+impl Normalizer for Replace {
+    fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
+        normalized.replace(&self.regex, &self.content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Range;
+
+    #[test]
+    fn replace() {
+        let original = "This is a ''test''";
+        let normalized = "This is a \"test\"";
+
+        let mut n = NormalizedString::from(original);
+        Replace::new("''", "\"").unwrap().normalize(&mut n).unwrap();
+
+        assert_eq!(&n.get(), &normalized);
+        // The replacement is aligned with the whole match
+        assert_eq!(n.convert_offsets(Range::Normalized(10..11)), Some(10..12));
+        assert_eq!(
+            n.get_range_original(Range::Normalized(10..16)),
+            Some("''test''")
+        );
+        assert_eq!(n.convert_offsets(Range::Original(12..16)), Some(11..15));
+    }
+
+    #[test]
+    fn replace_regex() {
+        let original = "This     is   a         test";
+        let normalized = "This is a test";
+
+        let mut n = NormalizedString::from(original);
+        Replace::new(ReplacePattern::Regex(r"\s+".into()), " ")
+            .unwrap()
+            .normalize(&mut n)
+            .unwrap();
+
+        assert_eq!(&n.get(), &normalized);
+        assert_eq!(n.get_range_original(Range::Normalized(4..5)), Some("     "));
+        assert_eq!(
+            n.get_range_original(Range::Normalized(10..14)),
+            Some("test")
+        );
+        assert_eq!(n.convert_offsets(Range::Original(24..28)), Some(10..14));
+    }
+
+    #[test]
+    fn replace_removal() {
+        let mut n = NormalizedString::from("a-b--c");
+        Replace::new("-", "").unwrap().normalize(&mut n).unwrap();
+
+        assert_eq!(n.get(), "abc");
+        assert_eq!(n.get_range_original(Range::Normalized(1..3)), Some("b--c"));
+    }
+
+    #[test]
+    fn serialization() {
+        let replace = Replace::new(ReplacePattern::Regex(r"\s+".into()), " ").unwrap();
+        let serialized = serde_json::to_string(&replace).unwrap();
+        assert_eq!(serialized, r#"{"pattern":{"Regex":"\\s+"},"content":" "}"#);
+        assert_eq!(
+            serde_json::from_str::<Replace>(&serialized).unwrap(),
+            replace
+        );
+
+        // Invalid regexes fail at deserialization
+        assert!(
+            serde_json::from_str::<Replace>(r#"{"pattern":{"Regex":"("},"content":" "}"#).is_err()
+        );
+    }
+}
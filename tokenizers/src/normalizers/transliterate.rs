@@ -0,0 +1,152 @@
+use crate::tokenizer::{remove_chars, NormalizedString, Normalizer, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Transliterates the text to ASCII: `Straße` becomes `Strasse`, `Ελλάδα` becomes `Ellada`,
+/// and CJK characters get their romanization (like `北京` that becomes `Bei Jing `).
+///
+/// Each character is transliterated on its own, so every produced character is aligned with
+/// the character it comes from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Transliterate {
+    /// Custom transliterations, with priority over the builtin ones
+    table: BTreeMap<char, String>,
+    /// Whether to use the builtin transliteration table
+    use_builtin: bool,
+    /// The replacement for the characters without any known transliteration. When `None`,
+    /// these characters are kept as they are.
+    unknown: Option<String>,
+}
+
+impl Default for Transliterate {
+    fn default() -> Self {
+        Self {
+            table: BTreeMap::new(),
+            use_builtin: true,
+            unknown: None,
+        }
+    }
+}
+
+impl Transliterate {
+    pub fn new(table: BTreeMap<char, String>, use_builtin: bool, unknown: Option<String>) -> Self {
+        Self {
+            table,
+            use_builtin,
+            unknown,
+        }
+    }
+
+    fn transliterate(&self, c: char) -> Vec<char> {
+        if let Some(s) = self.table.get(&c) {
+            return s.chars().collect();
+        }
+        if c.is_ascii() {
+            return vec![c];
+        }
+
+        let builtin = if self.use_builtin {
+            deunicode::deunicode_char(c)
+        } else {
+            None
+        };
+        match (builtin, &self.unknown) {
+            (Some(s), _) => s.chars().collect(),
+            (None, Some(s)) => s.chars().collect(),
+            (None, None) => vec![c],
+        }
+    }
+}
+
+#[typetag::serde]
+impl Normalizer for Transliterate {
+    fn normalize(&self, normalized: &mut NormalizedString) -> Result<()> {
+        let mut new_chars: Vec<(char, isize)> = vec![];
+        let mut initial_offset = 0;
+        normalized.for_each(|c| {
+            let transliterated = self.transliterate(c);
+            if transliterated.is_empty() {
+                // The char is removed
+                remove_chars(&mut new_chars, &mut initial_offset, 1);
+            } else {
+                new_chars.extend(
+                    transliterated
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, c)| (c, if i > 0 { 1 } else { 0 })),
+                );
+            }
+        });
+        normalized.transform(new_chars.into_iter(), initial_offset);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Range;
+
+    #[test]
+    fn transliterate() {
+        let mut n = NormalizedString::from("Straße Ελλάδα 北京");
+        Transliterate::default().normalize(&mut n).unwrap();
+
+        assert_eq!(n.get(), "Strasse Ellada Bei Jing ");
+        assert_eq!(n.get_range_original(Range::Normalized(4..6)), Some("ß"));
+        assert_eq!(
+            n.get_range_original(Range::Normalized(8..14)),
+            Some("Ελλάδα")
+        );
+        assert_eq!(n.get_range_original(Range::Normalized(15..19)), Some("北"));
+        assert_eq!(n.convert_offsets(Range::Normalized(19..24)), Some(15..16));
+    }
+
+    #[test]
+    fn combining_marks_are_removed() {
+        let mut n = NormalizedString::from("\u{301}e\u{301}");
+        Transliterate::default().normalize(&mut n).unwrap();
+
+        assert_eq!(n.get(), "e");
+        assert_eq!(n.convert_offsets(Range::Normalized(0..1)), Some(1..2));
+    }
+
+    #[test]
+    fn expansion_followed_by_removed_char() {
+        let mut table = BTreeMap::new();
+        table.insert('x', "".to_owned());
+        let mut n = NormalizedString::from("ßx!");
+        Transliterate::new(table, true, None)
+            .normalize(&mut n)
+            .unwrap();
+
+        assert_eq!(n.get(), "ss!");
+        assert_eq!(n.get_range_original(Range::Normalized(1..2)), Some("ß"));
+        assert_eq!(n.get_range_original(Range::Normalized(0..2)), Some("ß"));
+        assert_eq!(n.get_range_original(Range::Normalized(2..3)), Some("!"));
+    }
+
+    #[test]
+    fn custom_table() {
+        let mut table = BTreeMap::new();
+        table.insert('ß', "sz".to_owned());
+        table.insert('ö', "oe".to_owned());
+        let mut n = NormalizedString::from("Größe");
+        Transliterate::new(table, false, Some("?".into()))
+            .normalize(&mut n)
+            .unwrap();
+        assert_eq!(n.get(), "Groesze");
+
+        let mut n = NormalizedString::from("Ελλάδα");
+        Transliterate::new(BTreeMap::new(), false, Some("?".into()))
+            .normalize(&mut n)
+            .unwrap();
+        assert_eq!(n.get(), "??????");
+
+        let mut n = NormalizedString::from("Ελλάδα");
+        Transliterate::new(BTreeMap::new(), false, None)
+            .normalize(&mut n)
+            .unwrap();
+        assert_eq!(n.get(), "Ελλάδα");
+    }
+}
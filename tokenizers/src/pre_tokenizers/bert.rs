@@ -1,8 +1,8 @@
-use crate::tokenizer::{NormalizedString, Offsets, PreTokenizer, Result};
+use crate::tokenizer::{Offsets, PreTokenizedString, PreTokenizer, Result};
 use serde::{Deserialize, Serialize};
 use unicode_categories::UnicodeCategories;
 
-fn is_bert_punc(x: char) -> bool {
+pub(crate) fn is_bert_punc(x: char) -> bool {
     char::is_ascii_punctuation(&x) || x.is_punctuation()
 }
 
@@ -11,28 +11,27 @@ fn split_on<F: Fn(char) -> bool>(
     s: &str,
     should_split: F,
     include_split_token: bool,
-) -> Vec<(String, Offsets)> {
-    let mut words: Vec<(String, Offsets)> = vec![];
+) -> Vec<Offsets> {
+    let mut words: Vec<Offsets> = vec![];
     let mut offset = 0;
-    let mut word = Vec::with_capacity(50);
+    let mut word_len = 0;
     s.chars().for_each(|c| {
         if should_split(c) {
-            if !word.is_empty() {
-                let offsets = (offset - word.len(), offset);
-                words.push((word.drain(0..).collect::<String>(), offsets));
+            if word_len > 0 {
+                words.push((offset - word_len, offset));
+                word_len = 0;
             }
             if include_split_token {
-                words.push((c.to_string(), (offset, offset + 1)));
+                words.push((offset, offset + 1));
             }
-        } else if !should_split(c) {
-            word.push(c);
+        } else {
+            word_len += 1;
         }
         offset += 1;
     });
     // Don't forget the potential last word
-    if !word.is_empty() {
-        let offsets = (offset - word.len(), offset);
-        words.push((word.drain(0..).collect::<String>(), offsets));
+    if word_len > 0 {
+        words.push((offset - word_len, offset));
     }
 
     words
@@ -43,40 +42,37 @@ pub struct BertPreTokenizer;
 
 #[typetag::serde]
 impl PreTokenizer for BertPreTokenizer {
-    fn pre_tokenize(&self, normalized: &mut NormalizedString) -> Result<Vec<(String, Offsets)>> {
-        let mut split_tokens = vec![];
-        for (token, offsets) in split_on(normalized.get(), char::is_whitespace, false) {
-            split_tokens.extend(
-                split_on(&token, is_bert_punc, true)
-                    .into_iter()
-                    .map(|(tok, off)| (tok, (off.0 + offsets.0, off.1 + offsets.0))),
-            );
-        }
-        Ok(split_tokens)
+    fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> Result<()> {
+        pretokenized
+            .split(|_, normalized| Ok(split_on(normalized.get(), char::is_whitespace, false)))?;
+        pretokenized.split(|_, normalized| Ok(split_on(normalized.get(), is_bert_punc, true)))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tokenizer::OffsetReferential;
 
     #[test]
     fn basic() {
         let pretok = BertPreTokenizer;
-        let mut input = NormalizedString::from("Hey friend!     How are you?!?");
-        let res = pretok.pre_tokenize(&mut input).unwrap();
+        let mut pretokenized = PreTokenizedString::from("Hey friend!     How are you?!?");
+        pretok.pre_tokenize(&mut pretokenized).unwrap();
         assert_eq!(
-            &res,
-            &[
-                ("Hey".into(), (0, 3)),
-                ("friend".into(), (4, 10)),
-                ("!".into(), (10, 11)),
-                ("How".into(), (16, 19)),
-                ("are".into(), (20, 23)),
-                ("you".into(), (24, 27)),
-                ("?".into(), (27, 28)),
-                ("!".into(), (28, 29)),
-                ("?".into(), (29, 30)),
+            pretokenized
+                .get_splits(OffsetReferential::Normalized)
+                .unwrap(),
+            vec![
+                ("Hey", (0, 3)),
+                ("friend", (4, 10)),
+                ("!", (10, 11)),
+                ("How", (16, 19)),
+                ("are", (20, 23)),
+                ("you", (24, 27)),
+                ("?", (27, 28)),
+                ("!", (28, 29)),
+                ("?", (29, 30)),
             ]
         );
     }
@@ -1,10 +1,11 @@
 use crate::parallelism::*;
 use crate::tokenizer::{
-    Decoder, Encoding, NormalizedString, Offsets, PostProcessor, PreTokenizer, Result,
+    with_gaps, Decoder, Encoding, PostProcessor, PreTokenizedString, PreTokenizer, Result,
 };
 use onig::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
 
 fn bytes_char() -> HashMap<u8, char> {
     let mut bs: Vec<u8> = vec![];
@@ -29,16 +30,51 @@ fn bytes_char() -> HashMap<u8, char> {
         .collect()
 }
 
+/// The pattern used by GPT-2 to split the input
+pub static GPT2_PATTERN: &str =
+    r"'s|'t|'re|'ve|'m|'ll|'d| ?\p{L}+| ?\p{N}+| ?[^\s\p{L}\p{N}]+|\s+(?!\S)|\s+";
+
 lazy_static! {
-    static ref RE: Regex =
-        Regex::new(r"'s|'t|'re|'ve|'m|'ll|'d| ?\p{L}+| ?\p{N}+| ?[^\s\p{L}\p{N}]+|\s+(?!\S)|\s+")
-            .unwrap();
+    static ref RE: Regex = Regex::new(GPT2_PATTERN).unwrap();
     static ref BYTES_CHAR: HashMap<u8, char> = bytes_char();
     static ref CHAR_BYTES: HashMap<char, u8> =
         bytes_char().into_iter().map(|(c, b)| (b, c)).collect();
 }
 
+fn default_use_regex() -> bool {
+    true
+}
+
+/// We only serialize `use_regex` when it is not the default, to keep the existing files as is
+fn is_default_use_regex(use_regex: &bool) -> bool {
+    *use_regex == default_use_regex()
+}
+
+/// We use this custom deserializer to compile the custom pattern of `ByteLevel`. The options
+/// added after the first version are optional, to keep loading the existing files.
+#[doc(hidden)]
+#[derive(Deserialize)]
+struct ByteLevelDeserializer {
+    add_prefix_space: bool,
+    trim_offsets: bool,
+    #[serde(default = "default_use_regex")]
+    use_regex: bool,
+    #[serde(default)]
+    pattern: Option<String>,
+}
+
+impl TryFrom<ByteLevelDeserializer> for ByteLevel {
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    fn try_from(v: ByteLevelDeserializer) -> Result<Self> {
+        ByteLevel::new(v.add_prefix_space, v.trim_offsets)
+            .use_regex(v.use_regex)
+            .pattern(v.pattern)
+    }
+}
+
 #[derive(Serialize, Deserialize)]
+#[serde(try_from = "ByteLevelDeserializer")]
 /// Provides all the necessary steps to handle the BPE tokenization at the byte-level. Takes care
 /// of all the required processing steps to transform a UTF-8 string as needed before and after the
 /// BPE model does its job.
@@ -48,12 +84,24 @@ pub struct ByteLevel {
     add_prefix_space: bool,
     /// Whether the post processing step should trim offsets to avoid including whitespaces.
     trim_offsets: bool,
+    /// Whether to split the input using the regex. Without it, the whole input is kept as a
+    /// single piece, and only the byte-level mapping is applied.
+    #[serde(skip_serializing_if = "is_default_use_regex")]
+    use_regex: bool,
+    /// A custom pattern to split the input, instead of the one from GPT-2
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pattern: Option<String>,
+    #[serde(skip)]
+    regex: Option<Regex>,
 }
 impl Default for ByteLevel {
     fn default() -> Self {
         Self {
             add_prefix_space: true,
             trim_offsets: true,
+            use_regex: true,
+            pattern: None,
+            regex: None,
         }
     }
 }
@@ -63,6 +111,7 @@ impl ByteLevel {
         ByteLevel {
             add_prefix_space,
             trim_offsets,
+            ..Default::default()
         }
     }
 
@@ -79,73 +128,66 @@ impl ByteLevel {
         self.trim_offsets = v;
         self
     }
+
+    pub fn use_regex(mut self, v: bool) -> Self {
+        self.use_regex = v;
+        self
+    }
+
+    /// Set a custom pattern to split the input (like the one of a newer GPT vocabulary), or
+    /// `None` to use the GPT-2 one. This fails if the pattern is not a valid regex.
+    pub fn pattern(mut self, pattern: Option<String>) -> Result<Self> {
+        self.regex = match &pattern {
+            Some(pattern) => Some(Regex::new(pattern)?),
+            None => None,
+        };
+        self.pattern = pattern;
+        Ok(self)
+    }
 }
 
 /// As a `PreTokenizer`, `ByteLevel` is in charge of transforming all the unicode characters into
 /// their byte-level counterpart. It also splits the input according to the configured regex.
-// TODO: Give the ability to modify this regex
 #[typetag::serde]
 impl PreTokenizer for ByteLevel {
-    fn pre_tokenize(&self, normalized: &mut NormalizedString) -> Result<Vec<(String, Offsets)>> {
-        if self.add_prefix_space && !normalized.get().starts_with(' ') {
-            normalized.prepend(" ");
-        }
-
-        let positions = RE
-            .find_iter(normalized.get())
-            .map(|(start, end)| start..end)
-            .collect::<Vec<_>>();
-
-        let splits = positions
-            .into_maybe_par_iter()
-            .map(|range| {
-                // Process one of the splits
-                let slice = &normalized.get()[range];
-                let mut chars: Vec<(char, u8)> = Vec::with_capacity(slice.len());
-
-                let mut i = 0;
-                for cur_char in slice.chars() {
-                    let size = cur_char.len_utf8();
-                    let bytes = slice[i..i + size].as_bytes();
-                    i += size;
-                    chars.extend(
-                        bytes
-                            .iter()
-                            .enumerate()
-                            .map(|(i, b)| (BYTES_CHAR[b], if i > 0 { 1 } else { 0 })),
-                    );
-                }
-
-                chars
-            })
-            .collect::<Vec<_>>();
+    fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> Result<()> {
+        pretokenized.split(|i, normalized| {
+            // The space is added to the beginning of the input only, so it goes in the first
+            // split
+            if self.add_prefix_space && i == 0 && !normalized.get().starts_with(' ') {
+                normalized.prepend(" ");
+            }
 
-        // Update the NormalizedString
-        normalized.transform(
-            splits
-                .iter()
-                .flatten()
-                .map(|(c, changes)| (*c, *changes as isize)),
-            0,
-        );
+            let s = normalized.get();
+            let positions = if self.use_regex {
+                self.regex.as_ref().unwrap_or(&*RE).find_iter(s).collect()
+            } else if s.is_empty() {
+                vec![]
+            } else {
+                vec![(0, s.len())]
+            };
+
+            // Each byte becomes a char, all aligned with the char they come from. Each part of
+            // the string can be processed independently.
+            let transformations = with_gaps(s, positions.iter().copied())
+                .into_maybe_par_iter()
+                .map(|((start, end), _)| {
+                    s[start..end]
+                        .chars()
+                        .flat_map(|c| {
+                            let mut buf = [0; 4];
+                            let len = c.encode_utf8(&mut buf).len();
+                            (0..len).map(move |i| (BYTES_CHAR[&buf[i]], if i > 0 { 1 } else { 0 }))
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            normalized.transform(transformations.into_iter().flatten(), 0);
 
-        // Collect splits and their offsets
-        let mut total_len = 0;
-        Ok(splits
-            .into_iter()
-            .map(|s| {
-                let mut len = 0;
-                let s = s
-                    .into_iter()
-                    .map(|(c, _)| {
-                        len += 1;
-                        c
-                    })
-                    .collect::<String>();
-                total_len += len;
-                (s, (total_len - len, total_len))
-            })
-            .collect())
+            // Since each byte is now a char, the byte positions in the string we matched are
+            // the char positions in the transformed one
+            Ok(positions)
+        })
     }
 }
 
@@ -246,26 +288,34 @@ pub fn process_offsets(encoding: &mut Encoding, add_prefix_space: bool) {
 mod tests {
     use super::ByteLevel;
     use crate::tokenizer::{
-        Decoder, Encoding, NormalizedString, PostProcessor, PreTokenizer, Range,
+        Decoder, Encoding, OffsetReferential, PostProcessor, PreTokenizedString, PreTokenizer,
+        Range,
     };
 
+    fn pre_tokenize(bytelevel: &ByteLevel, s: &str) -> PreTokenizedString {
+        let mut pretokenized = PreTokenizedString::from(s);
+        bytelevel.pre_tokenize(&mut pretokenized).unwrap();
+        pretokenized
+    }
+
     #[test]
     fn pre_tokenization() {
         let bytelevel = ByteLevel::default().add_prefix_space(false);
-        let mut input = NormalizedString::from("Hello my friend, how is your day going?");
         assert_eq!(
-            bytelevel.pre_tokenize(&mut input).unwrap(),
+            pre_tokenize(&bytelevel, "Hello my friend, how is your day going?")
+                .get_splits(OffsetReferential::Normalized)
+                .unwrap(),
             vec![
-                ("Hello".into(), (0, 5)),
-                ("Ġmy".into(), (5, 8)),
-                ("Ġfriend".into(), (8, 15)),
-                (",".into(), (15, 16)),
-                ("Ġhow".into(), (16, 20)),
-                ("Ġis".into(), (20, 23)),
-                ("Ġyour".into(), (23, 28)),
-                ("Ġday".into(), (28, 32)),
-                ("Ġgoing".into(), (32, 38)),
-                ("?".into(), (38, 39))
+                ("Hello", (0, 5)),
+                ("Ġmy", (5, 8)),
+                ("Ġfriend", (8, 15)),
+                (",", (15, 16)),
+                ("Ġhow", (16, 20)),
+                ("Ġis", (20, 23)),
+                ("Ġyour", (23, 28)),
+                ("Ġday", (28, 32)),
+                ("Ġgoing", (32, 38)),
+                ("?", (38, 39))
             ]
         );
     }
@@ -296,22 +346,26 @@ mod tests {
             " Hello my friend, how is your day going?",
             "Hello my friend, how is your day going?",
         ] {
-            let mut normalized = NormalizedString::from(s);
-            let pretok = bytelevel.pre_tokenize(&mut normalized).unwrap();
-            assert_eq!(normalized.get(), "ĠHelloĠmyĠfriend,ĠhowĠisĠyourĠdayĠgoing?");
+            let pretokenized = pre_tokenize(&bytelevel, s);
             assert_eq!(
-                pretok,
+                pretokenized.get_normalized().get(),
+                "ĠHelloĠmyĠfriend,ĠhowĠisĠyourĠdayĠgoing?"
+            );
+            assert_eq!(
+                pretokenized
+                    .get_splits(OffsetReferential::Normalized)
+                    .unwrap(),
                 vec![
-                    ("ĠHello".into(), (0, 6)),
-                    ("Ġmy".into(), (6, 9)),
-                    ("Ġfriend".into(), (9, 16)),
-                    (",".into(), (16, 17)),
-                    ("Ġhow".into(), (17, 21)),
-                    ("Ġis".into(), (21, 24)),
-                    ("Ġyour".into(), (24, 29)),
-                    ("Ġday".into(), (29, 33)),
-                    ("Ġgoing".into(), (33, 39)),
-                    ("?".into(), (39, 40))
+                    ("ĠHello", (0, 6)),
+                    ("Ġmy", (6, 9)),
+                    ("Ġfriend", (9, 16)),
+                    (",", (16, 17)),
+                    ("Ġhow", (17, 21)),
+                    ("Ġis", (21, 24)),
+                    ("Ġyour", (24, 29)),
+                    ("Ġday", (29, 33)),
+                    ("Ġgoing", (33, 39)),
+                    ("?", (39, 40))
                 ]
             );
         }
@@ -327,9 +381,10 @@ mod tests {
 
         let bytelevel = ByteLevel::default().add_prefix_space(false);
         for sample in samples {
-            let mut input = NormalizedString::from(sample);
-            let pre_tokenized = bytelevel.pre_tokenize(&mut input).unwrap();
+            let pre_tokenized = pre_tokenize(&bytelevel, sample);
             let separated_tokens = pre_tokenized
+                .get_splits(OffsetReferential::Normalized)
+                .unwrap()
                 .iter()
                 .flat_map(|(token, _)| token.split("").map(|t| t.into()))
                 .collect::<Vec<_>>();
@@ -339,53 +394,51 @@ mod tests {
 
     #[test]
     fn handling_of_newlines() {
-        let mut input = NormalizedString::from("Hello there\nHello there");
         let bytelevel = ByteLevel::default().add_prefix_space(false);
-        let p = bytelevel.pre_tokenize(&mut input).unwrap();
+        let p = pre_tokenize(&bytelevel, "Hello there\nHello there");
 
         assert_eq!(
-            p,
+            p.get_splits(OffsetReferential::Normalized).unwrap(),
             vec![
-                ("Hello".into(), (0, 5)),
-                ("Ġthere".into(), (5, 11)),
-                ("Ċ".into(), (11, 12)),
-                ("Hello".into(), (12, 17)),
-                ("Ġthere".into(), (17, 23))
+                ("Hello", (0, 5)),
+                ("Ġthere", (5, 11)),
+                ("Ċ", (11, 12)),
+                ("Hello", (12, 17)),
+                ("Ġthere", (17, 23))
             ]
         );
     }
 
     #[test]
     fn handling_of_multiple_whitespaces() {
-        let mut input = NormalizedString::from("Hello there       dear");
         let bytelevel = ByteLevel::default().add_prefix_space(false);
-        let p = bytelevel.pre_tokenize(&mut input).unwrap();
+        let p = pre_tokenize(&bytelevel, "Hello there       dear");
 
         assert_eq!(
-            p,
+            p.get_splits(OffsetReferential::Normalized).unwrap(),
             vec![
-                ("Hello".into(), (0, 5)),
-                ("Ġthere".into(), (5, 11)),
-                ("ĠĠĠĠĠĠ".into(), (11, 17)),
-                ("Ġdear".into(), (17, 22))
+                ("Hello", (0, 5)),
+                ("Ġthere", (5, 11)),
+                ("ĠĠĠĠĠĠ", (11, 17)),
+                ("Ġdear", (17, 22))
             ]
         );
     }
 
     #[test]
     fn offsets_when_char_split_up() {
-        let mut input = NormalizedString::from("i⭢j");
         let bytelevel = ByteLevel::default().add_prefix_space(false);
-        let p = bytelevel.pre_tokenize(&mut input).unwrap();
+        let p = pre_tokenize(&bytelevel, "i⭢j");
 
         assert_eq!(
-            p,
-            vec![
-                ("i".into(), (0, 1)),
-                ("âŃ¢".into(), (1, 4)),
-                ("j".into(), (4, 5)),
-            ]
+            p.get_splits(OffsetReferential::Normalized).unwrap(),
+            vec![("i", (0, 1)), ("âŃ¢", (1, 4)), ("j", (4, 5)),]
+        );
+        assert_eq!(
+            p.get_splits(OffsetReferential::Original).unwrap(),
+            vec![("i", (0, 1)), ("âŃ¢", (1, 2)), ("j", (2, 3))]
         );
+        let input = p.get_normalized();
         assert_eq!(input.get(), "iâŃ¢j");
         assert_eq!(input.get_range_original(Range::Normalized(1..4)), Some("⭢"));
     }
@@ -458,4 +511,74 @@ mod tests {
             "Hello there dear friend! [PA D]"
         );
     }
+
+    #[test]
+    fn custom_pattern() {
+        // The pattern used by the newer GPT vocabularies, with case-insensitive contractions and
+        // groups of up to 3 digits
+        let pattern = r"(?i:'s|'t|'re|'ve|'m|'ll|'d)|[^\r\n\p{L}\p{N}]?\p{L}+|\p{N}{1,3}| ?[^\s\p{L}\p{N}]+[\r\n]*|\s*[\r\n]+|\s+(?!\S)|\s+";
+        let bytelevel = ByteLevel::default()
+            .add_prefix_space(false)
+            .pattern(Some(pattern.into()))
+            .unwrap();
+        assert_eq!(
+            pre_tokenize(&bytelevel, "I'LL pay 12345!")
+                .get_splits(OffsetReferential::Normalized)
+                .unwrap(),
+            vec![
+                ("I", (0, 1)),
+                ("'LL", (1, 4)),
+                ("Ġpay", (4, 8)),
+                ("Ġ", (8, 9)),
+                ("123", (9, 12)),
+                ("45", (12, 14)),
+                ("!", (14, 15)),
+            ]
+        );
+
+        assert!(ByteLevel::default().pattern(Some("(".into())).is_err());
+    }
+
+    #[test]
+    fn without_regex() {
+        let bytelevel = ByteLevel::default()
+            .add_prefix_space(false)
+            .use_regex(false);
+        assert_eq!(
+            pre_tokenize(&bytelevel, "Hello my friend")
+                .get_splits(OffsetReferential::Normalized)
+                .unwrap(),
+            vec![("HelloĠmyĠfriend", (0, 15))]
+        );
+        assert!(pre_tokenize(&bytelevel, "").is_empty());
+    }
+
+    #[test]
+    fn serialization() {
+        let bytelevel = ByteLevel::default()
+            .use_regex(false)
+            .pattern(Some(r"\s+".into()))
+            .unwrap();
+        let serialized = serde_json::to_string(&bytelevel).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"add_prefix_space":true,"trim_offsets":true,"use_regex":false,"pattern":"\\s+"}"#
+        );
+        let bytelevel: ByteLevel = serde_json::from_str(&serialized).unwrap();
+        assert!(!bytelevel.use_regex);
+        assert!(bytelevel.regex.is_some());
+
+        // The default is serialized just like before the new options existed
+        let serialized = serde_json::to_string(&ByteLevel::default()).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"add_prefix_space":true,"trim_offsets":true}"#
+        );
+
+        // The new options are optional
+        let bytelevel: ByteLevel =
+            serde_json::from_str(r#"{"add_prefix_space":true,"trim_offsets":false}"#).unwrap();
+        assert!(bytelevel.use_regex);
+        assert_eq!(bytelevel.pattern, None);
+    }
 }
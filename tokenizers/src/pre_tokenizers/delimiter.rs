@@ -1,4 +1,4 @@
-use crate::tokenizer::{NormalizedString, Offsets, PreTokenizer, Result};
+use crate::tokenizer::{PreTokenizedString, PreTokenizer, Result};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize)]
@@ -14,27 +14,28 @@ impl CharDelimiterSplit {
 
 #[typetag::serde]
 impl PreTokenizer for CharDelimiterSplit {
-    fn pre_tokenize(&self, normalized: &mut NormalizedString) -> Result<Vec<(String, Offsets)>> {
-        let mut words = vec![];
-        let mut word = Vec::with_capacity(1000);
-        let mut offset = 0;
+    fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> Result<()> {
+        pretokenized.split(|_, normalized| {
+            let mut words = vec![];
+            let mut word_len = 0;
+            let mut offset = 0;
 
-        normalized.get().chars().for_each(|c| {
-            if c == self.delimiter {
-                if !word.is_empty() {
-                    let offsets = (offset - word.len(), offset);
-                    words.push((word.drain(0..).collect::<String>(), offsets));
+            normalized.get().chars().for_each(|c| {
+                if c == self.delimiter {
+                    if word_len > 0 {
+                        words.push((offset - word_len, offset));
+                        word_len = 0;
+                    }
+                } else {
+                    word_len += 1;
                 }
-            } else {
-                word.push(c);
+                offset += 1;
+            });
+            if word_len > 0 {
+                words.push((offset - word_len, offset));
             }
-            offset += 1;
-        });
-        if !word.is_empty() {
-            let offsets = (offset - word.len(), offset);
-            words.push((word.drain(0..).collect::<String>(), offsets));
-        }
 
-        Ok(words)
+            Ok(words)
+        })
     }
 }
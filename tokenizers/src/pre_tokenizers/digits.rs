@@ -0,0 +1,143 @@
+use crate::tokenizer::{PreTokenizedString, PreTokenizer, Result};
+use serde::{Deserialize, Serialize};
+use unicode_categories::UnicodeCategories;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Splits the numbers from the rest of the text, and then each number in groups of
+/// `group_size` digits (individual digits by default). A `group_size` of 0 keeps each number
+/// whole. With `right_aligned`, the groups are aligned on the last digit, like the thousands:
+/// `1234567` gives `1`, `234`, `567` instead of `123`, `456`, `7`.
+///
+/// The parts of the text that are not digits are kept as they are, so this is usually used in
+/// a `Sequence`, after a pre-tokenizer splitting on whitespace.
+pub struct Digits {
+    group_size: usize,
+    right_aligned: bool,
+}
+
+impl Digits {
+    pub fn new(group_size: usize, right_aligned: bool) -> Self {
+        Self {
+            group_size,
+            right_aligned,
+        }
+    }
+
+    /// Returns the lengths of the groups for a number of `len` digits
+    fn groups(&self, len: usize) -> Vec<usize> {
+        if self.group_size == 0 || len <= self.group_size {
+            return vec![len];
+        }
+
+        let mut groups = vec![self.group_size; len / self.group_size];
+        let remainder = len % self.group_size;
+        if remainder > 0 {
+            if self.right_aligned {
+                groups.insert(0, remainder);
+            } else {
+                groups.push(remainder);
+            }
+        }
+        groups
+    }
+}
+
+impl Default for Digits {
+    fn default() -> Self {
+        Self::new(1, false)
+    }
+}
+
+#[typetag::serde]
+impl PreTokenizer for Digits {
+    fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> Result<()> {
+        pretokenized.split(|_, normalized| {
+            let chars = normalized.get().chars().collect::<Vec<_>>();
+
+            let mut splits = vec![];
+            let mut start = 0;
+            while start < chars.len() {
+                let is_digit = chars[start].is_number_decimal_digit();
+                let mut end = start + 1;
+                while end < chars.len() && chars[end].is_number_decimal_digit() == is_digit {
+                    end += 1;
+                }
+
+                if is_digit {
+                    let mut group_start = start;
+                    for len in self.groups(end - start) {
+                        let group_end = group_start + len;
+                        splits.push((group_start, group_end));
+                        group_start = group_end;
+                    }
+                } else {
+                    splits.push((start, end));
+                }
+                start = end;
+            }
+
+            Ok(splits)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::normalized_splits;
+
+    #[test]
+    fn individual_digits() {
+        assert_eq!(
+            normalized_splits(&Digits::default(), "Hé 123x٤٥"),
+            vec![
+                ("Hé ".into(), (0, 3)),
+                ("1".into(), (3, 4)),
+                ("2".into(), (4, 5)),
+                ("3".into(), (5, 6)),
+                ("x".into(), (6, 7)),
+                ("٤".into(), (7, 8)),
+                ("٥".into(), (8, 9)),
+            ]
+        );
+    }
+
+    #[test]
+    fn groups() {
+        assert_eq!(
+            normalized_splits(&Digits::new(3, false), "1234567 89"),
+            vec![
+                ("123".into(), (0, 3)),
+                ("456".into(), (3, 6)),
+                ("7".into(), (6, 7)),
+                (" ".into(), (7, 8)),
+                ("89".into(), (8, 10)),
+            ]
+        );
+        assert_eq!(
+            normalized_splits(&Digits::new(3, true), "$1234567"),
+            vec![
+                ("$".into(), (0, 1)),
+                ("1".into(), (1, 2)),
+                ("234".into(), (2, 5)),
+                ("567".into(), (5, 8)),
+            ]
+        );
+        assert_eq!(
+            normalized_splits(&Digits::new(0, false), "a1234b"),
+            vec![
+                ("a".into(), (0, 1)),
+                ("1234".into(), (1, 5)),
+                ("b".into(), (5, 6)),
+            ]
+        );
+    }
+
+    #[test]
+    fn serialization() {
+        let digits = Digits::new(3, true);
+        let serialized = serde_json::to_string(&digits).unwrap();
+        assert_eq!(serialized, r#"{"group_size":3,"right_aligned":true}"#);
+        assert_eq!(serde_json::from_str::<Digits>(&serialized).unwrap(), digits);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::tokenizer::{Decoder, NormalizedString, Offsets, PreTokenizer, Result};
+use crate::tokenizer::{Decoder, PreTokenizedString, PreTokenizer, Result};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize)]
@@ -26,32 +26,36 @@ impl Default for Metaspace {
 
 #[typetag::serde]
 impl PreTokenizer for Metaspace {
-    fn pre_tokenize(&self, normalized: &mut NormalizedString) -> Result<Vec<(String, Offsets)>> {
-        if self.add_prefix_space && !normalized.get().starts_with(' ') {
-            normalized.prepend(" ");
-        }
+    fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> Result<()> {
+        pretokenized.split(|i, normalized| {
+            // The space is added to the beginning of the input only, so it goes in the first
+            // split
+            if self.add_prefix_space && i == 0 && !normalized.get().starts_with(' ') {
+                normalized.prepend(" ");
+            }
 
-        let mut words = vec![];
-        let mut word = Vec::with_capacity(1000);
-        let mut offset = 0;
-        normalized.get().chars().for_each(|c| {
-            if c.is_whitespace() {
-                if !word.is_empty() {
-                    let offsets = (offset - word.len(), offset);
-                    words.push((word.drain(0..).collect::<String>(), offsets));
+            // Each word starts at a whitespace, that we then replace
+            let mut words = vec![];
+            let mut start = 0;
+            for (i, c) in normalized.get().chars().enumerate() {
+                if c.is_whitespace() && i > start {
+                    words.push((start, i));
+                    start = i;
                 }
-                word.push(self.replacement)
-            } else {
-                word.push(c);
             }
-            offset += 1;
-        });
-        if !word.is_empty() {
-            let offsets = (offset - word.len(), offset);
-            words.push((word.drain(0..).collect::<String>(), offsets));
-        }
+            if start < normalized.len() {
+                words.push((start, normalized.len()));
+            }
+            normalized.map(|c| {
+                if c.is_whitespace() {
+                    self.replacement
+                } else {
+                    c
+                }
+            });
 
-        Ok(words)
+            Ok(words)
+        })
     }
 }
 
@@ -80,30 +84,48 @@ impl Decoder for Metaspace {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tokenizer::{OffsetReferential, Range};
 
     #[test]
     fn basic() {
         let pretok = Metaspace::new('▁', true);
-        let mut input = NormalizedString::from("Hey friend!");
-        let res = pretok.pre_tokenize(&mut input).unwrap();
+        let mut pretokenized = PreTokenizedString::from("Hey friend!");
+        pretok.pre_tokenize(&mut pretokenized).unwrap();
+        assert_eq!(
+            pretokenized
+                .get_splits(OffsetReferential::Normalized)
+                .unwrap(),
+            vec![("▁Hey", (0, 4)), ("▁friend!", (4, 12))]
+        );
+        assert_eq!(
+            pretokenized
+                .get_splits(OffsetReferential::Original)
+                .unwrap(),
+            vec![("▁Hey", (0, 3)), ("▁friend!", (3, 11))]
+        );
+        // The whitespaces are replaced in the NormalizedString too
+        let normalized = pretokenized.get_normalized();
+        assert_eq!(normalized.get(), "▁Hey▁friend!");
         assert_eq!(
-            &res,
-            &[("▁Hey".into(), (0, 4)), ("▁friend!".into(), (4, 12)),]
+            normalized.get_range_original(Range::Normalized(4..12)),
+            Some(" friend!")
         );
     }
 
     #[test]
     fn multiple_spaces() {
         let pretok = Metaspace::new('▁', true);
-        let mut input = NormalizedString::from("Hey   friend!");
-        let res = pretok.pre_tokenize(&mut input).unwrap();
+        let mut pretokenized = PreTokenizedString::from("Hey   friend!");
+        pretok.pre_tokenize(&mut pretokenized).unwrap();
         assert_eq!(
-            &res,
-            &[
-                ("▁Hey".into(), (0, 4)),
-                ("▁".into(), (4, 5)),
-                ("▁".into(), (5, 6)),
-                ("▁friend!".into(), (6, 14)),
+            pretokenized
+                .get_splits(OffsetReferential::Normalized)
+                .unwrap(),
+            vec![
+                ("▁Hey", (0, 4)),
+                ("▁", (4, 5)),
+                ("▁", (5, 6)),
+                ("▁friend!", (6, 14)),
             ]
         );
     }
@@ -1,5 +1,14 @@
 pub mod bert;
 pub mod byte_level;
 pub mod delimiter;
+pub mod digits;
 pub mod metaspace;
+pub mod punctuation;
+pub mod sequence;
+pub mod source_code;
+pub mod split;
+pub mod unicode_scripts;
 pub mod whitespace;
+pub mod word_segmentation;
+
+pub use sequence::Sequence;
@@ -0,0 +1,94 @@
+use crate::pre_tokenizers::bert::is_bert_punc;
+use crate::pre_tokenizers::split::{split_with_behavior, SplitDelimiterBehavior};
+use crate::tokenizer::{Pattern, PreTokenizedString, PreTokenizer, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Splits on the punctuation (ASCII and Unicode), just like `BertPreTokenizer` does, but without
+/// splitting on whitespace. The behavior decides what to do with the punctuation marks: isolate
+/// each of them (the default), keep the runs together (`Contiguous`), attach them to the
+/// neighbouring word, or remove them.
+pub struct Punctuation {
+    behavior: SplitDelimiterBehavior,
+}
+
+impl Punctuation {
+    pub fn new(behavior: SplitDelimiterBehavior) -> Self {
+        Self { behavior }
+    }
+}
+
+impl Default for Punctuation {
+    fn default() -> Self {
+        Self::new(SplitDelimiterBehavior::Isolated)
+    }
+}
+
+#[typetag::serde]
+impl PreTokenizer for Punctuation {
+    fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> Result<()> {
+        pretokenized.split(|_, normalized| {
+            let s = normalized.get();
+            let matches = is_bert_punc.find_matches(s)?;
+            Ok(split_with_behavior(s, matches, self.behavior))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pre_tokenizers::sequence::Sequence;
+    use crate::pre_tokenizers::whitespace::WhitespaceSplit;
+    use crate::tokenizer::normalized_splits;
+
+    fn words(pretok: &dyn PreTokenizer, s: &str) -> Vec<String> {
+        normalized_splits(pretok, s)
+            .into_iter()
+            .map(|(word, _)| word)
+            .collect()
+    }
+
+    #[test]
+    fn behaviors() {
+        let s = "Wait...what?¿«Sí»";
+        let tests = vec![
+            (
+                SplitDelimiterBehavior::Isolated,
+                vec!["Wait", ".", ".", ".", "what", "?", "¿", "«", "Sí", "»"],
+            ),
+            (
+                SplitDelimiterBehavior::Contiguous,
+                vec!["Wait", "...", "what", "?¿«", "Sí", "»"],
+            ),
+            (
+                SplitDelimiterBehavior::MergedWithPrevious,
+                vec!["Wait.", ".", ".", "what?", "¿", "«", "Sí»"],
+            ),
+            (SplitDelimiterBehavior::Removed, vec!["Wait", "what", "Sí"]),
+        ];
+        for (behavior, expected) in tests {
+            assert_eq!(words(&Punctuation::new(behavior), s), expected);
+        }
+    }
+
+    #[test]
+    fn with_whitespace() {
+        let pretok = Sequence::new(vec![
+            Box::new(WhitespaceSplit),
+            Box::new(Punctuation::new(SplitDelimiterBehavior::Contiguous)),
+        ]);
+        assert_eq!(
+            normalized_splits(&pretok, "Hey, you!! Ça va?"),
+            vec![
+                ("Hey".into(), (0, 3)),
+                (",".into(), (3, 4)),
+                ("you".into(), (5, 8)),
+                ("!!".into(), (8, 10)),
+                ("Ça".into(), (11, 13)),
+                ("va".into(), (14, 16)),
+                ("?".into(), (16, 17)),
+            ]
+        );
+    }
+}
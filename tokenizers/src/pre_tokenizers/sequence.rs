@@ -0,0 +1,162 @@
+use crate::tokenizer::{PreTokenizedString, PreTokenizer, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+/// Allows concatenating multiple other PreTokenizer as a Sequence.
+/// The first PreTokenizer splits the whole NormalizedString, and then each of the following
+/// ones further splits each piece produced by the previous one.
+pub struct Sequence {
+    pretokenizers: Vec<Box<dyn PreTokenizer>>,
+}
+
+impl Sequence {
+    pub fn new(pretokenizers: Vec<Box<dyn PreTokenizer>>) -> Self {
+        Self { pretokenizers }
+    }
+}
+
+#[typetag::serde]
+impl PreTokenizer for Sequence {
+    fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> Result<()> {
+        for pretokenizer in &self.pretokenizers {
+            pretokenizer.pre_tokenize(pretokenized)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pre_tokenizers::byte_level::ByteLevel;
+    use crate::pre_tokenizers::delimiter::CharDelimiterSplit;
+    use crate::pre_tokenizers::metaspace::Metaspace;
+    use crate::pre_tokenizers::whitespace::{Whitespace, WhitespaceSplit};
+    use crate::tokenizer::{OffsetReferential, Range};
+
+    #[test]
+    fn split_pieces() {
+        let pretok = Sequence::new(vec![
+            Box::new(WhitespaceSplit),
+            Box::new(CharDelimiterSplit::new('-')),
+        ]);
+        let mut pretokenized = PreTokenizedString::from("Hey my-good  friend!");
+        pretok.pre_tokenize(&mut pretokenized).unwrap();
+        assert_eq!(
+            pretokenized
+                .get_splits(OffsetReferential::Normalized)
+                .unwrap(),
+            vec![
+                ("Hey", (0, 3)),
+                ("my", (4, 6)),
+                ("good", (7, 11)),
+                ("friend!", (13, 20)),
+            ]
+        );
+    }
+
+    #[test]
+    fn modified_pieces() {
+        // ByteLevel modifies each piece
+        let pretok = Sequence::new(vec![
+            Box::new(Whitespace),
+            Box::new(ByteLevel::default().add_prefix_space(false)),
+        ]);
+        let mut pretokenized = PreTokenizedString::from("Héllo wörld");
+        pretok.pre_tokenize(&mut pretokenized).unwrap();
+        assert_eq!(
+            pretokenized
+                .get_splits(OffsetReferential::Normalized)
+                .unwrap(),
+            vec![("HÃ©llo", (0, 6)), ("wÃ¶rld", (7, 13))]
+        );
+        assert_eq!(
+            pretokenized
+                .get_splits(OffsetReferential::Original)
+                .unwrap(),
+            vec![("HÃ©llo", (0, 5)), ("wÃ¶rld", (6, 11))]
+        );
+        let input = pretokenized.get_normalized();
+        assert_eq!(input.get(), "HÃ©llo wÃ¶rld");
+        assert_eq!(
+            input.get_range_original(Range::Normalized(7..13)),
+            Some("wörld")
+        );
+
+        // The prefix space of ByteLevel goes to the first piece only, as it is added to the
+        // beginning of the input
+        let pretok = Sequence::new(vec![
+            Box::new(WhitespaceSplit),
+            Box::new(ByteLevel::default()),
+        ]);
+        let mut pretokenized = PreTokenizedString::from("Hello my friend");
+        pretok.pre_tokenize(&mut pretokenized).unwrap();
+        assert_eq!(
+            pretokenized
+                .get_splits(OffsetReferential::Normalized)
+                .unwrap(),
+            vec![("ĠHello", (0, 6)), ("my", (7, 9)), ("friend", (10, 16))]
+        );
+        assert_eq!(
+            pretokenized
+                .get_splits(OffsetReferential::Original)
+                .unwrap(),
+            vec![("ĠHello", (0, 5)), ("my", (6, 8)), ("friend", (9, 15))]
+        );
+
+        // Metaspace replaces the whitespaces
+        let pretok = Sequence::new(vec![
+            Box::new(Metaspace::default()),
+            Box::new(CharDelimiterSplit::new('-')),
+        ]);
+        let mut pretokenized = PreTokenizedString::from("Hey my-friend");
+        pretok.pre_tokenize(&mut pretokenized).unwrap();
+        assert_eq!(
+            pretokenized
+                .get_splits(OffsetReferential::Normalized)
+                .unwrap(),
+            vec![("▁Hey", (0, 4)), ("▁my", (4, 7)), ("friend", (8, 14)),]
+        );
+        let input = pretokenized.get_normalized();
+        assert_eq!(input.get(), "▁Hey▁my-friend");
+        assert_eq!(
+            input.get_range_original(Range::Normalized(4..7)),
+            Some(" my")
+        );
+
+        // Just like for ByteLevel, the prefix space of Metaspace goes to the first piece only
+        let pretok = Sequence::new(vec![
+            Box::new(CharDelimiterSplit::new('-')),
+            Box::new(Metaspace::default()),
+        ]);
+        let mut pretokenized = PreTokenizedString::from("state-of-art");
+        pretok.pre_tokenize(&mut pretokenized).unwrap();
+        assert_eq!(
+            pretokenized
+                .get_splits(OffsetReferential::Normalized)
+                .unwrap(),
+            vec![("▁state", (0, 6)), ("of", (7, 9)), ("art", (10, 13))]
+        );
+        assert_eq!(
+            pretokenized
+                .get_splits(OffsetReferential::Original)
+                .unwrap(),
+            vec![("▁state", (0, 5)), ("of", (6, 8)), ("art", (9, 12))]
+        );
+        assert_eq!(pretokenized.get_normalized().get(), "▁state-of-art");
+    }
+
+    #[test]
+    fn serialization() {
+        let pretok = Sequence::new(vec![Box::new(WhitespaceSplit), Box::new(Whitespace)]);
+        let serialized = serde_json::to_string(&pretok).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"pretokenizers":[{"type":"WhitespaceSplit"},{"type":"Whitespace"}]}"#
+        );
+        let pretok: Sequence = serde_json::from_str(&serialized).unwrap();
+        let mut pretokenized = PreTokenizedString::from("Hey friend!");
+        pretok.pre_tokenize(&mut pretokenized).unwrap();
+        assert_eq!(pretokenized.len(), 3);
+    }
+}
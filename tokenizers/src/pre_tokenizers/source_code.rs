@@ -0,0 +1,214 @@
+use crate::tokenizer::{PreTokenizedString, PreTokenizer, Result};
+use serde::{Deserialize, Serialize};
+use unicode_categories::UnicodeCategories;
+
+/// The kind of a char, deciding where the splits happen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Newline,
+    Space,
+    Underscore,
+    Upper,
+    /// Any other letter (lowercase or without case)
+    Lower,
+    Digit,
+    /// The brackets and separators, always isolated
+    Bracket,
+    /// Any other symbol, grouped with the following ones
+    Operator,
+}
+
+impl Kind {
+    fn of(c: char) -> Self {
+        match c {
+            '\n' | '\r' => Kind::Newline,
+            '_' => Kind::Underscore,
+            '(' | ')' | '[' | ']' | '{' | '}' | ',' | ';' | '"' | '\'' | '`' => Kind::Bracket,
+            c if c.is_whitespace() => Kind::Space,
+            c if c.is_uppercase() => Kind::Upper,
+            c if c.is_alphabetic() => Kind::Lower,
+            c if c.is_numeric() => Kind::Digit,
+            _ => Kind::Operator,
+        }
+    }
+
+    fn is_letter(self) -> bool {
+        self == Kind::Upper || self == Kind::Lower
+    }
+}
+
+/// Splits source code into pieces fit for the code models:
+///   - The identifiers are split on the case (`parseHTTPResponse` gives `parse`, `HTTP`,
+///     `Response`), on the underscores (`snake_case` gives `snake`, `_`, `case`) and between
+///     letters and digits (`utf8` gives `utf`, `8`). The hyphens of `kebab-case` are operators.
+///   - The whitespace is kept, each run (like an indentation) as a single piece, and each line
+///     break as its own piece (`\r\n` being a single one).
+///   - The runs of operators (like `+=` or `->`) are kept together, while the brackets, quotes
+///     and separators are always isolated.
+///
+/// Nothing is ever removed, so the splits can be given to `ByteLevel` (without its regex) in a
+/// `Sequence`, and the code is restored exactly by the `ByteLevel` decoder.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SourceCode;
+
+/// Returns whether there is a split between the chars at `i - 1` and `i`
+fn is_boundary(chars: &[char], kinds: &[Kind], i: usize) -> bool {
+    let (prev, cur) = (kinds[i - 1], kinds[i]);
+    match (prev, cur) {
+        (Kind::Newline, Kind::Newline) => !(chars[i - 1] == '\r' && chars[i] == '\n'),
+        (Kind::Bracket, _) | (_, Kind::Bracket) => true,
+        // camelCase
+        (Kind::Lower, Kind::Upper) => true,
+        // The last capital of an acronym starts a new word: `HTTPResponse`
+        (Kind::Upper, Kind::Upper) => kinds.get(i + 1) == Some(&Kind::Lower),
+        (prev, cur) if prev.is_letter() && cur.is_letter() => false,
+        (prev, cur) => prev != cur,
+    }
+}
+
+#[typetag::serde]
+impl PreTokenizer for SourceCode {
+    fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> Result<()> {
+        pretokenized.split(|_, normalized| {
+            let chars = normalized.get().chars().collect::<Vec<_>>();
+            // The combining marks stay with the char before them
+            let mut kinds: Vec<Kind> = Vec::with_capacity(chars.len());
+            for c in &chars {
+                let kind = match kinds.last() {
+                    Some(last) if c.is_mark() => *last,
+                    _ => Kind::of(*c),
+                };
+                kinds.push(kind);
+            }
+
+            let mut splits = vec![];
+            let mut start = 0;
+            for end in 1..=chars.len() {
+                if end == chars.len() || is_boundary(&chars, &kinds, end) {
+                    splits.push((start, end));
+                    start = end;
+                }
+            }
+
+            Ok(splits)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pre_tokenizers::byte_level::ByteLevel;
+    use crate::pre_tokenizers::Sequence;
+    use crate::tokenizer::{normalized_splits, Decoder, OffsetReferential, Range};
+
+    fn pre_tokenize(pretok: &dyn PreTokenizer, s: &str) -> PreTokenizedString {
+        let mut pretokenized = PreTokenizedString::from(s);
+        pretok.pre_tokenize(&mut pretokenized).unwrap();
+        pretokenized
+    }
+
+    fn split(s: &str) -> Vec<String> {
+        normalized_splits(&SourceCode, s)
+            .into_iter()
+            .map(|(s, _)| s)
+            .collect()
+    }
+
+    #[test]
+    fn identifiers() {
+        assert_eq!(
+            split("parseHTTPResponse XMLHttpRequest2 __init__ utf8Décodeur kebab-case"),
+            vec![
+                "parse",
+                "HTTP",
+                "Response",
+                " ",
+                "XML",
+                "Http",
+                "Request",
+                "2",
+                " ",
+                "__",
+                "init",
+                "__",
+                " ",
+                "utf",
+                "8",
+                "Décodeur",
+                " ",
+                "kebab",
+                "-",
+                "case"
+            ]
+        );
+        assert_eq!(split("ÉTÉ_été"), vec!["ÉTÉ", "_", "été"]);
+        assert_eq!(split(""), Vec::<String>::new());
+
+        // The offsets are in chars
+        assert_eq!(
+            pre_tokenize(&SourceCode, "étéRésumé")
+                .get_splits(OffsetReferential::Original)
+                .unwrap(),
+            vec![("été", (0, 3)), ("Résumé", (3, 9))]
+        );
+    }
+
+    #[test]
+    fn code() {
+        let code = "def f(x):\r\n    if x >= 10:\n\t\treturn x->y != [1, 2]\n";
+        assert_eq!(
+            split(code),
+            vec![
+                "def", " ", "f", "(", "x", ")", ":", "\r\n", "    ", "if", " ", "x", " ", ">=",
+                " ", "10", ":", "\n", "\t\t", "return", " ", "x", "->", "y", " ", "!=", " ", "[",
+                "1", ",", " ", "2", "]", "\n"
+            ]
+        );
+    }
+
+    #[test]
+    fn byte_level() {
+        let pretok = Sequence::new(vec![
+            Box::new(SourceCode),
+            Box::new(ByteLevel::new(false, false).use_regex(false)),
+        ]);
+        let code = "if (größe <= 2) {\n    return myVar_1;\n}";
+        let pretokenized = pre_tokenize(&pretok, code);
+        let splits = pretokenized
+            .get_splits(OffsetReferential::Normalized)
+            .unwrap();
+        assert_eq!(
+            splits.iter().take(6).map(|(s, _)| *s).collect::<Vec<_>>(),
+            vec!["if", "Ġ", "(", "grÃ¶ÃŁe", "Ġ", "<="]
+        );
+
+        // Each split maps back to the original code
+        assert_eq!(
+            pretokenized
+                .get_splits(OffsetReferential::Original)
+                .unwrap()[3],
+            ("grÃ¶ÃŁe", (4, 9))
+        );
+        assert_eq!(
+            pretokenized
+                .get_normalized()
+                .get_range_original(Range::Normalized(splits[3].1 .0..splits[3].1 .1)),
+            Some("größe")
+        );
+        let decoded = ByteLevel::default()
+            .decode(splits.into_iter().map(|(s, _)| s.to_owned()).collect())
+            .unwrap();
+        assert_eq!(decoded, code);
+    }
+
+    #[test]
+    fn serialization() {
+        let serialized = serde_json::to_string(&SourceCode).unwrap();
+        assert_eq!(serialized, "null");
+        assert_eq!(
+            serde_json::from_str::<SourceCode>(&serialized).unwrap(),
+            SourceCode
+        );
+    }
+}
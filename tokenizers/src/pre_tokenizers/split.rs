@@ -0,0 +1,307 @@
+use crate::tokenizer::{Offsets, Pattern, PreTokenizedString, PreTokenizer, Result};
+use onig::Regex;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+
+/// Represents the different patterns that `Split` can use
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SplitPattern {
+    String(String),
+    Regex(String),
+}
+
+impl From<String> for SplitPattern {
+    fn from(v: String) -> Self {
+        SplitPattern::String(v)
+    }
+}
+
+impl From<&str> for SplitPattern {
+    fn from(v: &str) -> Self {
+        SplitPattern::String(v.to_owned())
+    }
+}
+
+/// What to do with the delimiters (the parts matching the pattern) when splitting.
+///
+/// For example, when splitting `the-final--countdown` on `-`:
+///   - `Removed`: `the`, `final`, `countdown`
+///   - `Isolated`: `the`, `-`, `final`, `-`, `-`, `countdown`
+///   - `MergedWithPrevious`: `the-`, `final-`, `-`, `countdown`
+///   - `MergedWithNext`: `the`, `-final`, `-`, `-countdown`
+///   - `Contiguous`: `the`, `-`, `final`, `--`, `countdown`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SplitDelimiterBehavior {
+    Removed,
+    Isolated,
+    MergedWithPrevious,
+    MergedWithNext,
+    Contiguous,
+}
+
+/// Apply the behavior on the given matches (as returned by a `Pattern`), returning the offsets
+/// of the words
+fn apply_behavior(behavior: SplitDelimiterBehavior, matches: Vec<(Offsets, bool)>) -> Vec<Offsets> {
+    match behavior {
+        SplitDelimiterBehavior::Removed => matches
+            .into_iter()
+            .filter(|(_, is_match)| !is_match)
+            .map(|(offsets, _)| offsets)
+            .collect(),
+        SplitDelimiterBehavior::Isolated => {
+            matches.into_iter().map(|(offsets, _)| offsets).collect()
+        }
+        SplitDelimiterBehavior::MergedWithPrevious => {
+            let mut previous_match = true;
+            let mut words: Vec<Offsets> = vec![];
+            for ((start, end), is_match) in matches {
+                match words.last_mut() {
+                    Some(last) if is_match && !previous_match => last.1 = end,
+                    _ => words.push((start, end)),
+                }
+                previous_match = is_match;
+            }
+            words
+        }
+        SplitDelimiterBehavior::MergedWithNext => {
+            let mut next_match = true;
+            let mut words: Vec<Offsets> = vec![];
+            for ((start, end), is_match) in matches.into_iter().rev() {
+                match words.last_mut() {
+                    Some(last) if is_match && !next_match => last.0 = start,
+                    _ => words.push((start, end)),
+                }
+                next_match = is_match;
+            }
+            words.reverse();
+            words
+        }
+        SplitDelimiterBehavior::Contiguous => {
+            let mut previous_match = false;
+            let mut words: Vec<Offsets> = vec![];
+            for ((start, end), is_match) in matches {
+                match words.last_mut() {
+                    Some(last) if is_match && previous_match => last.1 = end,
+                    _ => words.push((start, end)),
+                }
+                previous_match = is_match;
+            }
+            words
+        }
+    }
+}
+
+/// Split the given string using the matches of a `Pattern` (in bytes), and the behavior. This
+/// returns the offsets of the words in chars, as expected from a `PreTokenizer`.
+pub(crate) fn split_with_behavior(
+    s: &str,
+    matches: Vec<(Offsets, bool)>,
+    behavior: SplitDelimiterBehavior,
+) -> Vec<Offsets> {
+    // The matches are expressed in bytes, so we need to convert them to chars
+    let mut chars = vec![0; s.len() + 1];
+    for (i, (b, _)) in s.char_indices().enumerate() {
+        chars[b] = i;
+    }
+    chars[s.len()] = s.chars().count();
+
+    apply_behavior(behavior, matches)
+        .into_iter()
+        .map(|(start, end)| (chars[start], chars[end]))
+        .collect()
+}
+
+/// We use this custom deserializer to provide the value for `regex` for `Split`
+#[doc(hidden)]
+#[derive(Deserialize)]
+struct SplitDeserializer {
+    pattern: SplitPattern,
+    behavior: SplitDelimiterBehavior,
+    invert: bool,
+}
+
+impl TryFrom<SplitDeserializer> for Split {
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    fn try_from(v: SplitDeserializer) -> Result<Self> {
+        Self::new(v.pattern, v.behavior, v.invert)
+    }
+}
+
+/// Splits the input using the given pattern, handling the delimiters according to the
+/// `SplitDelimiterBehavior`. With `invert`, the parts matching the pattern are the words, and
+/// everything else is a delimiter.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(try_from = "SplitDeserializer")]
+pub struct Split {
+    pattern: SplitPattern,
+    behavior: SplitDelimiterBehavior,
+    invert: bool,
+    #[serde(skip)]
+    regex: Regex,
+}
+
+impl Clone for Split {
+    fn clone(&self) -> Self {
+        Self::new(self.pattern.clone(), self.behavior, self.invert).unwrap()
+    }
+}
+
+impl PartialEq for Split {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern
+            && self.behavior == other.behavior
+            && self.invert == other.invert
+    }
+}
+
+impl Split {
+    pub fn new<I: Into<SplitPattern>>(
+        pattern: I,
+        behavior: SplitDelimiterBehavior,
+        invert: bool,
+    ) -> Result<Self> {
+        let pattern: SplitPattern = pattern.into();
+        let regex = match &pattern {
+            SplitPattern::String(s) => Regex::new(&regex::escape(s))?,
+            SplitPattern::Regex(r) => Regex::new(r)?,
+        };
+
+        Ok(Self {
+            pattern,
+            behavior,
+            invert,
+            regex,
+        })
+    }
+}
+
+#[typetag::serde]
+impl PreTokenizer for Split {
+    fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> Result<()> {
+        pretokenized.split(|_, normalized| {
+            let s = normalized.get();
+            let mut matches = (&self.regex).find_matches(s)?;
+            if self.invert {
+                matches
+                    .iter_mut()
+                    .for_each(|(_, is_match)| *is_match = !*is_match);
+            }
+
+            Ok(split_with_behavior(s, matches, self.behavior))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::normalized_splits;
+
+    #[test]
+    fn behaviors() {
+        let tests = vec![
+            (
+                SplitDelimiterBehavior::Removed,
+                vec![("the", (0, 3)), ("final", (4, 9)), ("countdown", (11, 20))],
+            ),
+            (
+                SplitDelimiterBehavior::Isolated,
+                vec![
+                    ("the", (0, 3)),
+                    ("-", (3, 4)),
+                    ("final", (4, 9)),
+                    ("-", (9, 10)),
+                    ("-", (10, 11)),
+                    ("countdown", (11, 20)),
+                ],
+            ),
+            (
+                SplitDelimiterBehavior::MergedWithPrevious,
+                vec![
+                    ("the-", (0, 4)),
+                    ("final-", (4, 10)),
+                    ("-", (10, 11)),
+                    ("countdown", (11, 20)),
+                ],
+            ),
+            (
+                SplitDelimiterBehavior::MergedWithNext,
+                vec![
+                    ("the", (0, 3)),
+                    ("-final", (3, 9)),
+                    ("-", (9, 10)),
+                    ("-countdown", (10, 20)),
+                ],
+            ),
+            (
+                SplitDelimiterBehavior::Contiguous,
+                vec![
+                    ("the", (0, 3)),
+                    ("-", (3, 4)),
+                    ("final", (4, 9)),
+                    ("--", (9, 11)),
+                    ("countdown", (11, 20)),
+                ],
+            ),
+        ];
+
+        for (behavior, expected) in tests {
+            let pretok = Split::new("-", behavior, false).unwrap();
+            let expected = expected
+                .into_iter()
+                .map(|(s, o)| (s.to_owned(), o))
+                .collect::<Vec<_>>();
+            assert_eq!(normalized_splits(&pretok, "the-final--countdown"), expected);
+        }
+    }
+
+    #[test]
+    fn regex_and_invert() {
+        let pretok = Split::new(
+            SplitPattern::Regex(r"\s+".into()),
+            SplitDelimiterBehavior::Removed,
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            normalized_splits(&pretok, "Hëllo  wörld"),
+            vec![("Hëllo".into(), (0, 5)), ("wörld".into(), (7, 12))]
+        );
+
+        // With invert, the matches are the words
+        let pretok = Split::new(
+            SplitPattern::Regex(r"\d+".into()),
+            SplitDelimiterBehavior::Removed,
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            normalized_splits(&pretok, "né 12 et 345"),
+            vec![("12".into(), (3, 5)), ("345".into(), (9, 12))]
+        );
+        assert_eq!(normalized_splits(&pretok, ""), vec![]);
+    }
+
+    #[test]
+    fn serialization() {
+        let pretok = Split::new(
+            SplitPattern::Regex(r"\s+".into()),
+            SplitDelimiterBehavior::MergedWithNext,
+            true,
+        )
+        .unwrap();
+        let serialized = serde_json::to_string(&pretok).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"pattern":{"Regex":"\\s+"},"behavior":"MergedWithNext","invert":true}"#
+        );
+        assert_eq!(serde_json::from_str::<Split>(&serialized).unwrap(), pretok);
+
+        // Invalid regexes fail at deserialization
+        assert!(serde_json::from_str::<Split>(
+            r#"{"pattern":{"Regex":"("},"behavior":"Removed","invert":false}"#
+        )
+        .is_err());
+    }
+}
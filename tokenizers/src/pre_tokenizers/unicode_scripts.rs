@@ -0,0 +1,206 @@
+use crate::tokenizer::{Offsets, PreTokenizedString, PreTokenizer, Result};
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use unicode_script::{Script, UnicodeScript};
+
+#[derive(Debug)]
+pub enum UnicodeScriptsError {
+    /// The name is not the full name of a Unicode script
+    UnknownScript(String),
+    /// A family must contain at least one script
+    EmptyFamily,
+}
+
+impl std::fmt::Display for UnicodeScriptsError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            UnicodeScriptsError::UnknownScript(name) => {
+                write!(fmt, "Unknown Unicode script '{}'", name)
+            }
+            UnicodeScriptsError::EmptyFamily => write!(fmt, "Script families can't be empty"),
+        }
+    }
+}
+impl std::error::Error for UnicodeScriptsError {}
+
+/// We use this custom deserializer to provide the value for `scripts` for `UnicodeScripts`
+#[doc(hidden)]
+#[derive(Deserialize)]
+struct UnicodeScriptsDeserializer {
+    families: Vec<Vec<String>>,
+}
+
+impl TryFrom<UnicodeScriptsDeserializer> for UnicodeScripts {
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    fn try_from(v: UnicodeScriptsDeserializer) -> Result<Self> {
+        Self::new(v.families)
+    }
+}
+
+/// Splits the text each time the Unicode script changes, like the `split_by_unicode_script`
+/// option of SentencePiece: `GPU加速する` gives `GPU` and `加速する`.
+///
+/// The chars of the `Common` and `Inherited` scripts (whitespace, digits, punctuation,
+/// combining marks, ...) never start a new word: they stay with the text before them. The
+/// scripts of a same family (given by their full name, like `Hiragana`) are considered as
+/// one, so that Japanese is not split between its kanji and kanas by default.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(try_from = "UnicodeScriptsDeserializer")]
+pub struct UnicodeScripts {
+    families: Vec<Vec<String>>,
+    /// The scripts of each family
+    #[serde(skip)]
+    scripts: Vec<Vec<Script>>,
+}
+
+impl Clone for UnicodeScripts {
+    fn clone(&self) -> Self {
+        Self::new(self.families.clone()).unwrap()
+    }
+}
+
+impl PartialEq for UnicodeScripts {
+    fn eq(&self, other: &Self) -> bool {
+        self.families == other.families
+    }
+}
+
+impl Default for UnicodeScripts {
+    fn default() -> Self {
+        Self::new(vec![vec![
+            "Han".into(),
+            "Hiragana".into(),
+            "Katakana".into(),
+        ]])
+        .unwrap()
+    }
+}
+
+impl UnicodeScripts {
+    pub fn new(families: Vec<Vec<String>>) -> Result<Self> {
+        let scripts = families
+            .iter()
+            .map(|family| {
+                if family.is_empty() {
+                    return Err(UnicodeScriptsError::EmptyFamily);
+                }
+                family
+                    .iter()
+                    .map(|name| {
+                        Script::from_full_name(name)
+                            .ok_or_else(|| UnicodeScriptsError::UnknownScript(name.clone()))
+                    })
+                    .collect()
+            })
+            .collect::<std::result::Result<_, _>>()?;
+
+        Ok(Self { families, scripts })
+    }
+
+    /// Returns the script of the given char, or `None` if it can be part of any script. The
+    /// scripts of a family are all represented by the first one.
+    fn script(&self, c: char) -> Option<Script> {
+        match c.script() {
+            Script::Common | Script::Inherited => None,
+            script => Some(
+                self.scripts
+                    .iter()
+                    .find(|family| family.contains(&script))
+                    .map_or(script, |family| family[0]),
+            ),
+        }
+    }
+}
+
+#[typetag::serde]
+impl PreTokenizer for UnicodeScripts {
+    fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> Result<()> {
+        pretokenized.split(|_, normalized| {
+            let mut splits: Vec<Offsets> = vec![];
+            let mut last_script = None;
+            for (i, c) in normalized.get().chars().enumerate() {
+                let script = self.script(c);
+                // We only start a new word when the script changes, so the chars that can be
+                // part of any script at the beginning are kept with the first script that
+                // follows them.
+                let same_word = script.is_none() || last_script.is_none() || script == last_script;
+                match splits.last_mut() {
+                    Some(offsets) if same_word => offsets.1 = i + 1,
+                    _ => splits.push((i, i + 1)),
+                }
+                if script.is_some() {
+                    last_script = script;
+                }
+            }
+
+            Ok(splits)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::normalized_splits;
+
+    #[test]
+    fn scripts() {
+        let pretok = UnicodeScripts::default();
+        assert_eq!(
+            normalized_splits(&pretok, "GPU加速する"),
+            vec![("GPU".into(), (0, 3)), ("加速する".into(), (3, 7))]
+        );
+        // Common and Inherited chars stay with what comes before
+        assert_eq!(
+            normalized_splits(&pretok, "Привет, мир! Hello 世界ー123"),
+            vec![
+                ("Привет, мир! ".into(), (0, 13)),
+                ("Hello ".into(), (13, 19)),
+                ("世界ー123".into(), (19, 25)),
+            ]
+        );
+        assert_eq!(
+            normalized_splits(&pretok, "12 e\u{301}té"),
+            vec![("12 e\u{301}té".into(), (0, 7))]
+        );
+        assert_eq!(normalized_splits(&pretok, ""), vec![]);
+    }
+
+    #[test]
+    fn families() {
+        let pretok = UnicodeScripts::new(vec![]).unwrap();
+        assert_eq!(
+            normalized_splits(&pretok, "日本語のテキスト"),
+            vec![
+                ("日本語".into(), (0, 3)),
+                ("の".into(), (3, 4)),
+                ("テキスト".into(), (4, 8)),
+            ]
+        );
+
+        let pretok = UnicodeScripts::new(vec![vec!["Latin".into(), "Greek".into()]]).unwrap();
+        assert_eq!(
+            normalized_splits(&pretok, "αβ test кот"),
+            vec![("αβ test ".into(), (0, 8)), ("кот".into(), (8, 11))]
+        );
+
+        assert!(UnicodeScripts::new(vec![vec!["Klingon".into()]]).is_err());
+        assert!(UnicodeScripts::new(vec![vec![]]).is_err());
+    }
+
+    #[test]
+    fn serialization() {
+        let pretok = UnicodeScripts::default();
+        let serialized = serde_json::to_string(&pretok).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"families":[["Han","Hiragana","Katakana"]]}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<UnicodeScripts>(&serialized).unwrap(),
+            pretok
+        );
+        assert!(serde_json::from_str::<UnicodeScripts>(r#"{"families":[["Klingon"]]}"#).is_err());
+    }
+}
@@ -0,0 +1,428 @@
+use crate::normalizers::isolate::CharClass;
+use crate::tokenizer::{PreTokenizedString, PreTokenizer, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    fs::File,
+    io::{BufRead, BufReader},
+};
+
+/// The cost added to an unknown char, on top of the cost of the rarest word, when searching
+/// the lattice. This makes sure we always prefer the known words.
+const UNKNOWN_PENALTY: f64 = 10.0;
+
+#[derive(Debug)]
+pub enum WordSegmentationError {
+    /// The line of the word list (starting at 1) has an invalid count
+    InvalidCount(usize),
+}
+
+impl std::fmt::Display for WordSegmentationError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            WordSegmentationError::InvalidCount(line) => {
+                write!(fmt, "Invalid count on line {} of the word list", line)
+            }
+        }
+    }
+}
+impl std::error::Error for WordSegmentationError {}
+
+/// Where the words used by `WordSegmentation` come from. Each word comes with a count, only
+/// used by the `Lattice` algorithm to prefer the most frequent words.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum WordList {
+    /// A local file, with one word per line, optionally followed by a tab and its count. The
+    /// file is read again when deserializing.
+    File(String),
+    /// The words with their counts
+    Inline(Vec<(String, u64)>),
+}
+
+impl WordList {
+    /// Read the words and their counts
+    fn read(&self) -> Result<Vec<(String, u64)>> {
+        match self {
+            WordList::File(path) => {
+                let file = BufReader::new(File::open(path)?);
+                let mut words = vec![];
+                for (i, line) in file.lines().enumerate() {
+                    let line = line?;
+                    let mut parts = line.trim_end().splitn(2, '\t');
+                    let word = parts.next().unwrap_or("");
+                    if word.is_empty() {
+                        continue;
+                    }
+                    let count = match parts.next() {
+                        Some(count) => count
+                            .trim()
+                            .parse()
+                            .map_err(|_| WordSegmentationError::InvalidCount(i + 1))?,
+                        None => 1,
+                    };
+                    words.push((word.to_owned(), count));
+                }
+                Ok(words)
+            }
+            WordList::Inline(words) => Ok(words.clone()),
+        }
+    }
+}
+
+/// How to segment a run of chars into words
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SegmentationAlgorithm {
+    /// Take the longest known word each time (forward maximum matching)
+    MaxMatch,
+    /// Find the most likely sequence of words, using their counts
+    Lattice,
+}
+
+/// Returns the byte offset of each char of `s`, followed by the length of `s`, so that the
+/// words can be looked up as slices of `s`
+fn char_bounds(s: &str) -> Vec<usize> {
+    s.char_indices()
+        .map(|(b, _)| b)
+        .chain(std::iter::once(s.len()))
+        .collect()
+}
+
+/// The words with their cost (their negative log probability), for fast lookups
+#[derive(Debug, Clone, Default)]
+struct Dictionary {
+    costs: HashMap<String, f64>,
+    /// The length of the longest word, in chars
+    max_len: usize,
+    /// The cost of a char that is not in any word
+    unknown_cost: f64,
+}
+
+impl Dictionary {
+    fn new(words: Vec<(String, u64)>) -> Self {
+        let total = words.iter().map(|(_, count)| *count as f64).sum::<f64>();
+        let mut dictionary = Dictionary::default();
+        for (word, count) in words {
+            // Words without any count are still known words, just as rare as possible
+            let cost = -((count.max(1) as f64) / total.max(1.0)).ln();
+            dictionary.max_len = dictionary.max_len.max(word.chars().count());
+            dictionary.unknown_cost = dictionary.unknown_cost.max(cost);
+            let entry = dictionary.costs.entry(word).or_insert(cost);
+            *entry = entry.min(cost);
+        }
+        dictionary.unknown_cost += UNKNOWN_PENALTY;
+        dictionary
+    }
+
+    /// Returns the cost of the given word, if it is known
+    fn cost(&self, word: &str) -> Option<f64> {
+        self.costs.get(word).copied()
+    }
+
+    /// Segment the text by taking the longest known word each time, returning the length of
+    /// each word in chars
+    fn max_match(&self, s: &str) -> Vec<usize> {
+        let bounds = char_bounds(s);
+        let n_chars = bounds.len() - 1;
+        let mut lengths = vec![];
+        let mut start = 0;
+        while start < n_chars {
+            let longest = std::cmp::min(self.max_len, n_chars - start);
+            let len = (2..=longest)
+                .rev()
+                .find(|len| self.cost(&s[bounds[start]..bounds[start + len]]).is_some())
+                .unwrap_or(1);
+            lengths.push(len);
+            start += len;
+        }
+        lengths
+    }
+
+    /// Segment the text into the sequence of words with the lowest total cost, returning the
+    /// length of each word in chars. Unknown chars are kept as single chars.
+    fn lattice(&self, s: &str) -> Vec<usize> {
+        let bounds = char_bounds(s);
+        let n_chars = bounds.len() - 1;
+        // For each position, the best cost to reach it, and the length of the last word
+        let mut best: Vec<(f64, usize)> = vec![(f64::INFINITY, 0); n_chars + 1];
+        best[0] = (0.0, 0);
+        for end in 1..=n_chars {
+            let longest = std::cmp::min(self.max_len, end);
+            for len in (1..=std::cmp::max(longest, 1)).rev() {
+                let cost = match self.cost(&s[bounds[end - len]..bounds[end]]) {
+                    Some(cost) => cost,
+                    None if len == 1 => self.unknown_cost,
+                    None => continue,
+                };
+                let total = best[end - len].0 + cost;
+                if total < best[end].0 {
+                    best[end] = (total, len);
+                }
+            }
+        }
+
+        let mut lengths = vec![];
+        let mut end = n_chars;
+        while end > 0 {
+            let len = best[end].1;
+            lengths.push(len);
+            end -= len;
+        }
+        lengths.reverse();
+        lengths
+    }
+}
+
+fn default_classes() -> Vec<CharClass> {
+    vec![
+        CharClass::Chinese,
+        CharClass::Hiragana,
+        CharClass::Katakana,
+        CharClass::Thai,
+    ]
+}
+
+/// We use this custom deserializer to load the words of `WordSegmentation`
+#[doc(hidden)]
+#[derive(Deserialize)]
+struct WordSegmentationDeserializer {
+    words: WordList,
+    algorithm: SegmentationAlgorithm,
+    #[serde(default = "default_classes")]
+    classes: Vec<CharClass>,
+}
+
+impl TryFrom<WordSegmentationDeserializer> for WordSegmentation {
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    fn try_from(v: WordSegmentationDeserializer) -> Result<Self> {
+        Ok(Self::new(v.words, v.algorithm)?.classes(v.classes))
+    }
+}
+
+/// Segments the text of the languages written without spaces (Chinese, Japanese, Thai, ...)
+/// into words, using a list of known words. Each run of chars from the given `classes` is
+/// segmented with the chosen `SegmentationAlgorithm`, and the unknown chars are kept as
+/// single chars. The rest of the text is kept as it is, so this is usually used in a
+/// `Sequence`, after a pre-tokenizer splitting on whitespace.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "WordSegmentationDeserializer")]
+pub struct WordSegmentation {
+    words: WordList,
+    algorithm: SegmentationAlgorithm,
+    classes: Vec<CharClass>,
+    #[serde(skip)]
+    dictionary: Dictionary,
+}
+
+impl PartialEq for WordSegmentation {
+    fn eq(&self, other: &Self) -> bool {
+        self.words == other.words
+            && self.algorithm == other.algorithm
+            && self.classes == other.classes
+    }
+}
+
+impl WordSegmentation {
+    /// Load the given words. By default, the Chinese, Japanese and Thai chars are segmented.
+    pub fn new(words: WordList, algorithm: SegmentationAlgorithm) -> Result<Self> {
+        let dictionary = Dictionary::new(words.read()?);
+        Ok(Self {
+            words,
+            algorithm,
+            classes: default_classes(),
+            dictionary,
+        })
+    }
+
+    /// Set the classes of chars to segment
+    pub fn classes(mut self, classes: Vec<CharClass>) -> Self {
+        self.classes = classes;
+        self
+    }
+
+    fn segment(&self, s: &str) -> Vec<usize> {
+        match self.algorithm {
+            SegmentationAlgorithm::MaxMatch => self.dictionary.max_match(s),
+            SegmentationAlgorithm::Lattice => self.dictionary.lattice(s),
+        }
+    }
+}
+
+#[typetag::serde]
+impl PreTokenizer for WordSegmentation {
+    fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> Result<()> {
+        pretokenized.split(|_, normalized| {
+            let s = normalized.get();
+            let chars = s.chars().collect::<Vec<_>>();
+            let bounds = char_bounds(s);
+            let in_classes = |c: char| self.classes.iter().any(|class| class.contains(c));
+
+            let mut splits = vec![];
+            let mut start = 0;
+            while start < chars.len() {
+                let segment = in_classes(chars[start]);
+                let mut end = start + 1;
+                while end < chars.len() && in_classes(chars[end]) == segment {
+                    end += 1;
+                }
+
+                if segment {
+                    let mut word_start = start;
+                    for len in self.segment(&s[bounds[start]..bounds[end]]) {
+                        let word_end = word_start + len;
+                        splits.push((word_start, word_end));
+                        word_start = word_end;
+                    }
+                } else {
+                    splits.push((start, end));
+                }
+                start = end;
+            }
+
+            Ok(splits)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::normalized_splits;
+    use std::io::Write;
+
+    fn inline(words: &[&str]) -> WordList {
+        WordList::Inline(words.iter().map(|w| (w.to_string(), 1)).collect())
+    }
+
+    #[test]
+    fn max_match() {
+        let pretok = WordSegmentation::new(
+            inline(&["北京", "大学", "北京大学", "生活", "研究", "研究生", "生命"]),
+            SegmentationAlgorithm::MaxMatch,
+        )
+        .unwrap();
+        assert_eq!(
+            normalized_splits(&pretok, "我在北京大学生活 ok"),
+            vec![
+                ("我".into(), (0, 1)),
+                ("在".into(), (1, 2)),
+                ("北京大学".into(), (2, 6)),
+                ("生活".into(), (6, 8)),
+                (" ok".into(), (8, 11)),
+            ]
+        );
+        // The longest match is not always the best
+        assert_eq!(
+            normalized_splits(&pretok, "研究生命"),
+            vec![("研究生".into(), (0, 3)), ("命".into(), (3, 4))]
+        );
+    }
+
+    #[test]
+    fn lattice() {
+        let pretok = WordSegmentation::new(
+            inline(&["研究", "研究生", "生命", "สวัสดี", "ครับ"]),
+            SegmentationAlgorithm::Lattice,
+        )
+        .unwrap();
+        assert_eq!(
+            normalized_splits(&pretok, "研究生命的起源"),
+            vec![
+                ("研究".into(), (0, 2)),
+                ("生命".into(), (2, 4)),
+                ("的".into(), (4, 5)),
+                ("起".into(), (5, 6)),
+                ("源".into(), (6, 7)),
+            ]
+        );
+        assert_eq!(
+            normalized_splits(&pretok, "Thai: สวัสดีครับ"),
+            vec![
+                ("Thai: ".into(), (0, 6)),
+                ("สวัสดี".into(), (6, 12)),
+                ("ครับ".into(), (12, 16)),
+            ]
+        );
+
+        // The counts decide between the possible segmentations
+        let words = vec![("ab", 1), ("c", 1), ("a", 10), ("bc", 10)];
+        let pretok = WordSegmentation::new(
+            WordList::Inline(words.into_iter().map(|(w, c)| (w.into(), c)).collect()),
+            SegmentationAlgorithm::Lattice,
+        )
+        .unwrap()
+        .classes(vec![CharClass::Range('a' as u32, 'z' as u32)]);
+        assert_eq!(
+            normalized_splits(&pretok, "abc"),
+            vec![("a".into(), (0, 1)), ("bc".into(), (1, 3))]
+        );
+    }
+
+    #[test]
+    fn word_list_file() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(file, "研究\t5\n研究生\n\n生命\t3").unwrap();
+        let path = file.path().to_str().unwrap().to_owned();
+
+        let pretok =
+            WordSegmentation::new(WordList::File(path.clone()), SegmentationAlgorithm::Lattice)
+                .unwrap();
+        assert_eq!(
+            normalized_splits(&pretok, "研究生命"),
+            vec![("研究".into(), (0, 2)), ("生命".into(), (2, 4))]
+        );
+
+        // Only the path is serialized, and the file is loaded again
+        let serialized = serde_json::to_string(&pretok).unwrap();
+        assert_eq!(
+            serialized,
+            format!(
+                r#"{{"words":{{"File":{}}},"algorithm":"Lattice","classes":["Chinese","Hiragana","Katakana","Thai"]}}"#,
+                serde_json::to_string(&path).unwrap()
+            )
+        );
+        let deserialized = serde_json::from_str::<WordSegmentation>(&serialized).unwrap();
+        assert_eq!(deserialized, pretok);
+        assert_eq!(
+            normalized_splits(&deserialized, "研究生命"),
+            normalized_splits(&pretok, "研究生命")
+        );
+
+        writeln!(file, "生活\tmany").unwrap();
+        assert!(
+            WordSegmentation::new(WordList::File(path), SegmentationAlgorithm::Lattice).is_err()
+        );
+        assert!(WordSegmentation::new(
+            WordList::File("missing-words.txt".into()),
+            SegmentationAlgorithm::MaxMatch
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn serialization() {
+        let pretok = WordSegmentation::new(inline(&["生命"]), SegmentationAlgorithm::MaxMatch)
+            .unwrap()
+            .classes(vec![CharClass::Chinese]);
+        let serialized = serde_json::to_string(&pretok).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"words":{"Inline":[["生命",1]]},"algorithm":"MaxMatch","classes":["Chinese"]}"#
+        );
+        let deserialized = serde_json::from_str::<WordSegmentation>(&serialized).unwrap();
+        assert_eq!(deserialized, pretok);
+        assert_eq!(
+            normalized_splits(&deserialized, "生命"),
+            vec![("生命".into(), (0, 2))]
+        );
+
+        // The classes are optional
+        let pretok: WordSegmentation =
+            serde_json::from_str(r#"{"words":{"Inline":[]},"algorithm":"Lattice"}"#).unwrap();
+        assert_eq!(
+            normalized_splits(&pretok, "生命"),
+            vec![("生".into(), (0, 1)), ("命".into(), (1, 2))]
+        );
+    }
+}
@@ -0,0 +1,432 @@
+/// A run of consecutive alignments
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Run {
+    /// `len` chars, each aligned with one char of the original string, starting at `start`
+    Identity { start: usize, len: usize },
+    /// `len` chars, all aligned with the same span of the original string
+    Constant { span: (usize, usize), len: usize },
+}
+
+impl Run {
+    fn len(&self) -> usize {
+        match self {
+            Run::Identity { len, .. } => *len,
+            Run::Constant { len, .. } => *len,
+        }
+    }
+
+    fn get(&self, i: usize) -> (usize, usize) {
+        match self {
+            Run::Identity { start, .. } => (start + i, start + i + 1),
+            Run::Constant { span, .. } => *span,
+        }
+    }
+
+    fn last(&self) -> (usize, usize) {
+        self.get(self.len() - 1)
+    }
+
+    fn set_len(&mut self, new_len: usize) {
+        match self {
+            Run::Identity { len, .. } => *len = new_len,
+            Run::Constant { len, .. } => *len = new_len,
+        }
+    }
+
+    /// Return the sub-run `[start, end)`
+    fn slice(&self, start: usize, end: usize) -> Run {
+        match self {
+            Run::Identity { start: s, .. } => Run::Identity {
+                start: s + start,
+                len: end - start,
+            },
+            Run::Constant { span, .. } => Run::Constant {
+                span: *span,
+                len: end - start,
+            },
+        }
+    }
+}
+
+/// The alignments of a `NormalizedString`: for each char of the normalized string, the span
+/// `(start, end)` of the original string it comes from.
+///
+/// Instead of storing one span per char, we store runs of spans. A string that has not been
+/// modified is represented by a single `Identity` run, and each edit usually adds only a few
+/// runs. The runs are always built greedily by `push`, so two `Alignments` representing the
+/// same spans are equal.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Alignments {
+    /// The runs, with the index of the first char they represent
+    runs: Vec<(usize, Run)>,
+    len: usize,
+}
+
+impl Alignments {
+    /// The alignments of a string of `len` chars, that has not been modified
+    pub fn identity(len: usize) -> Self {
+        let mut alignments = Self::default();
+        alignments.push_run(Run::Identity { start: 0, len });
+        alignments
+    }
+
+    /// The number of chars
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Return the index of the run containing the char at the given index
+    fn find_run(&self, index: usize) -> usize {
+        match self.runs.binary_search_by_key(&index, |(start, _)| *start) {
+            Ok(run) => run,
+            Err(run) => run - 1,
+        }
+    }
+
+    /// Return the alignment of the char at the given index
+    pub fn get(&self, index: usize) -> Option<(usize, usize)> {
+        if index >= self.len {
+            return None;
+        }
+        let (start, run) = &self.runs[self.find_run(index)];
+        Some(run.get(index - start))
+    }
+
+    /// Return a `Cursor`, to efficiently get the alignments at increasing indexes
+    pub fn cursor(&self) -> Cursor<'_> {
+        Cursor {
+            alignments: self,
+            run: 0,
+        }
+    }
+
+    pub fn last(&self) -> Option<(usize, usize)> {
+        self.runs.last().map(|(_, run)| run.last())
+    }
+
+    /// Convert a range of the original string to the range of the normalized chars aligned
+    /// with it. We look at the alignments in order, until one ends after the range: the range
+    /// starts at the last char starting before it, and ends after the last char ending in it.
+    ///
+    /// This walks the runs instead of the chars, so an untouched string only needs one step.
+    pub fn original_to_normalized(&self, range: std::ops::Range<usize>) -> (usize, usize) {
+        let (mut start, mut end) = (0, 0);
+        for (run_start, run) in &self.runs {
+            match run {
+                Run::Identity { start: s, len } => {
+                    // The number of chars ending before the end of the range
+                    let taken = std::cmp::min(*len, range.end.saturating_sub(*s));
+                    if taken == 0 {
+                        break;
+                    }
+                    if *s <= range.start {
+                        start = run_start + std::cmp::min(taken - 1, range.start - s);
+                    }
+                    end = run_start + taken;
+                    if taken < *len {
+                        break;
+                    }
+                }
+                Run::Constant { span, len } => {
+                    if span.1 > range.end {
+                        break;
+                    }
+                    if span.0 <= range.start {
+                        start = run_start + len - 1;
+                    }
+                    end = run_start + len;
+                }
+            }
+        }
+        (start, end)
+    }
+
+    /// Return the alignments of each char, as a `Vec`
+    #[cfg(test)]
+    pub fn to_vec(&self) -> Vec<(usize, usize)> {
+        self.runs
+            .iter()
+            .flat_map(|(_, run)| (0..run.len()).map(move |i| run.get(i)))
+            .collect()
+    }
+
+    /// Add the alignment of a new char at the end
+    pub fn push(&mut self, (start, end): (usize, usize)) {
+        let len = self.len;
+        self.len += 1;
+
+        if let Some((_, last)) = self.runs.last_mut() {
+            let tail = last.last();
+            match last {
+                Run::Identity { start: s, len: l } if *s + *l == start && end == start + 1 => {
+                    *l += 1;
+                    return;
+                }
+                Run::Constant { span, len: l } if *span == (start, end) => {
+                    *l += 1;
+                    return;
+                }
+                Run::Identity { len: l, .. } if tail == (start, end) => {
+                    // The last char of the identity run joins this one in a constant run
+                    if *l == 1 {
+                        self.runs.pop();
+                    } else {
+                        *l -= 1;
+                    }
+                    self.runs.push((
+                        len - 1,
+                        Run::Constant {
+                            span: (start, end),
+                            len: 2,
+                        },
+                    ));
+                    return;
+                }
+                _ => {}
+            }
+        }
+
+        let run = if end == start + 1 {
+            Run::Identity { start, len: 1 }
+        } else {
+            Run::Constant {
+                span: (start, end),
+                len: 1,
+            }
+        };
+        self.runs.push((len, run));
+    }
+
+    /// Add a whole run at the end. This is equivalent to pushing each of its chars, but we
+    /// only need to push the first two to know in which run all the others end up.
+    fn push_run(&mut self, run: Run) {
+        let n = run.len();
+        for i in 0..std::cmp::min(n, 2) {
+            self.push(run.get(i));
+        }
+        if n > 2 {
+            let (_, last) = self.runs.last_mut().unwrap();
+            last.set_len(last.len() + n - 2);
+            self.len += n - 2;
+        }
+    }
+
+    /// Add all the given alignments at the end
+    pub fn extend(&mut self, other: &Alignments) {
+        for (_, run) in &other.runs {
+            self.push_run(*run);
+        }
+    }
+
+    /// Return the alignments of the chars in `[start, end)`
+    pub fn slice(&self, start: usize, end: usize) -> Option<Alignments> {
+        if start > end || end > self.len {
+            return None;
+        }
+
+        let mut alignments = Alignments::default();
+        if start == end {
+            return Some(alignments);
+        }
+        for (run_start, run) in &self.runs[self.find_run(start)..] {
+            if *run_start >= end {
+                break;
+            }
+            let run_end = run_start + run.len();
+            let from = start.saturating_sub(*run_start);
+            let to = std::cmp::min(end, run_end) - run_start;
+            alignments.push_run(run.slice(from, to));
+        }
+        Some(alignments)
+    }
+
+    /// Split the alignments in two at the given index, returning `[at, len)` and keeping
+    /// `[0, at)` in self.
+    pub fn split_off(&mut self, at: usize) -> Alignments {
+        let after = self.slice(at, self.len).unwrap_or_default();
+        *self = self.slice(0, at).unwrap_or_default();
+        after
+    }
+
+    /// Shift all the alignments by `delta`
+    pub fn shift(&mut self, delta: isize) {
+        let shift = |v: usize| (v as isize + delta) as usize;
+        for (_, run) in self.runs.iter_mut() {
+            match run {
+                Run::Identity { start, .. } => *start = shift(*start),
+                Run::Constant { span, .. } => *span = (shift(span.0), shift(span.1)),
+            }
+        }
+    }
+}
+
+/// Gives the alignments of chars at increasing indexes, without searching for the
+/// containing run each time.
+pub(crate) struct Cursor<'a> {
+    alignments: &'a Alignments,
+    /// The index of the current run
+    run: usize,
+}
+
+impl<'a> Cursor<'a> {
+    /// Return the alignment of the char at the given index
+    pub fn get(&mut self, index: usize) -> Option<(usize, usize)> {
+        if index >= self.alignments.len {
+            return None;
+        }
+        let runs = &self.alignments.runs;
+        if runs[self.run].0 > index {
+            // We went backward, so we need to search again
+            self.run = self.alignments.find_run(index);
+        } else {
+            while runs[self.run].0 + runs[self.run].1.len() <= index {
+                self.run += 1;
+            }
+        }
+        let (start, run) = &runs[self.run];
+        Some(run.get(index - start))
+    }
+}
+
+impl From<Vec<(usize, usize)>> for Alignments {
+    fn from(v: Vec<(usize, usize)>) -> Self {
+        let mut alignments = Alignments::default();
+        for alignment in v {
+            alignments.push(alignment);
+        }
+        alignments
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identity() {
+        let alignments = Alignments::identity(4);
+        assert_eq!(alignments.runs.len(), 1);
+        assert_eq!(alignments.to_vec(), vec![(0, 1), (1, 2), (2, 3), (3, 4)]);
+        assert_eq!(alignments.get(2), Some((2, 3)));
+        assert_eq!(alignments.get(4), None);
+        assert_eq!(alignments, Alignments::from(alignments.to_vec()));
+        assert_eq!(Alignments::identity(0).len(), 0);
+    }
+
+    #[test]
+    fn runs() {
+        let spans = vec![
+            (0, 1),
+            (0, 1),
+            (1, 2),
+            (2, 3),
+            (2, 4),
+            (2, 4),
+            (4, 5),
+            (5, 6),
+        ];
+        let alignments = Alignments::from(spans.clone());
+        assert_eq!(
+            alignments.runs,
+            vec![
+                (
+                    0,
+                    Run::Constant {
+                        span: (0, 1),
+                        len: 2
+                    }
+                ),
+                (2, Run::Identity { start: 1, len: 2 }),
+                (
+                    4,
+                    Run::Constant {
+                        span: (2, 4),
+                        len: 2
+                    }
+                ),
+                (6, Run::Identity { start: 4, len: 2 }),
+            ]
+        );
+        assert_eq!(alignments.to_vec(), spans);
+        let mut cursor = alignments.cursor();
+        for (i, span) in spans.iter().enumerate() {
+            assert_eq!(alignments.get(i), Some(*span));
+            assert_eq!(cursor.get(i), Some(*span));
+        }
+        assert_eq!(cursor.get(1), Some((0, 1)));
+        assert_eq!(cursor.get(8), None);
+        assert_eq!(alignments.last(), Some((5, 6)));
+    }
+
+    #[test]
+    fn slice_and_split() {
+        let spans = vec![
+            (0, 1),
+            (0, 1),
+            (1, 2),
+            (2, 3),
+            (2, 4),
+            (2, 4),
+            (4, 5),
+            (5, 6),
+        ];
+        let alignments = Alignments::from(spans.clone());
+
+        for start in 0..spans.len() {
+            for end in start..=spans.len() {
+                let slice = alignments.slice(start, end).unwrap();
+                // The slice is built just like if we pushed each alignment
+                assert_eq!(slice, Alignments::from(spans[start..end].to_vec()));
+            }
+        }
+        assert_eq!(alignments.slice(2, 9), None);
+
+        let mut first = alignments.clone();
+        let second = first.split_off(3);
+        assert_eq!(first.to_vec(), spans[..3].to_vec());
+        assert_eq!(second.to_vec(), spans[3..].to_vec());
+
+        first.extend(&second);
+        assert_eq!(first, alignments);
+    }
+
+    #[test]
+    fn original_to_normalized() {
+        let spans = vec![
+            (0, 1),
+            (0, 1),
+            (1, 2),
+            (2, 3),
+            (2, 4),
+            (2, 4),
+            (4, 5),
+            (5, 6),
+            (8, 9),
+        ];
+        let alignments = Alignments::from(spans.clone());
+
+        // The same as looking at each char
+        for start in 0..10 {
+            for end in start..10 {
+                let (mut s, mut e) = (0, 0);
+                for (i, span) in spans.iter().enumerate().take_while(|(_, a)| end >= a.1) {
+                    if span.0 <= start {
+                        s = i;
+                    }
+                    if span.1 <= end {
+                        e = i + 1;
+                    }
+                }
+                assert_eq!(alignments.original_to_normalized(start..end), (s, e));
+            }
+        }
+        assert_eq!(Alignments::identity(0).original_to_normalized(0..2), (0, 0));
+    }
+
+    #[test]
+    fn shift() {
+        let mut alignments = Alignments::from(vec![(2, 3), (3, 4), (3, 5)]);
+        alignments.shift(-2);
+        assert_eq!(alignments.to_vec(), vec![(0, 1), (1, 2), (1, 3)]);
+    }
+}
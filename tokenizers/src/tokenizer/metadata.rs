@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Metadata saved along with the `Tokenizer`. This is never used during the tokenization, but
+/// lets us keep track of where a tokenizer comes from.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Metadata {
+    /// Free-form key/values
+    #[serde(default)]
+    pub values: BTreeMap<String, String>,
+    /// Information about the last training of the model, if it was trained with this library
+    #[serde(default)]
+    pub training: Option<TrainingProvenance>,
+}
+
+/// Describes how a model has been trained
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrainingProvenance {
+    /// The configuration of the `Trainer`, as serialized
+    pub trainer: serde_json::Value,
+    /// The files used as input. This stays empty when training from pre-computed word counts.
+    pub files: Vec<FileProvenance>,
+    /// The date of the training (UTC, RFC 3339)
+    pub date: String,
+}
+
+/// Describes one of the files used for training
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileProvenance {
+    /// The name of the file, as provided for training
+    pub name: String,
+    /// The size in bytes
+    pub size: u64,
+    /// The SHA-256 of the content, as a lowercase hex string
+    pub sha256: String,
+}
+
+/// Returns the current date, formatted using RFC 3339
+pub(crate) fn now() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    format_date(secs)
+}
+
+/// Format the given timestamp (seconds since the UNIX epoch) using RFC 3339
+fn format_date(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let secs = secs % 86400;
+
+    // Convert the number of days to a civil date
+    // cf. http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3600,
+        (secs % 3600) / 60,
+        secs % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn date() {
+        assert_eq!(format_date(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_date(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(format_date(1_602_000_000), "2020-10-06T16:00:00Z");
+    }
+}
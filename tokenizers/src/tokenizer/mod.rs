@@ -12,8 +12,13 @@
 pub use crate::utils::iter::LinesWithEnding;
 use crate::utils::iter::ResultShunt;
 pub use crate::utils::padding::{pad_encodings, PaddingDirection, PaddingParams, PaddingStrategy};
+use crate::utils::progress::Progress;
+pub use crate::utils::progress::{
+    CancellationToken, ProgressCallback, TrainingCancelled, TrainingPhase, TrainingProgress,
+};
 pub use crate::utils::truncation::{truncate_encodings, TruncationParams, TruncationStrategy};
 use indicatif::{ProgressBar, ProgressStyle};
+use sha2::{Digest, Sha256};
 use std::{
     collections::HashMap,
     fs::File,
@@ -23,13 +28,22 @@ use std::{
 };
 
 mod added_vocabulary;
+mod alignments;
 mod encoding;
+mod metadata;
 mod normalizer;
+mod pattern;
+mod pre_tokenizer;
 mod serialization;
+mod word_counts;
 
 pub use added_vocabulary::*;
 pub use encoding::*;
+pub use metadata::*;
 pub use normalizer::*;
+pub use pattern::*;
+pub use pre_tokenizer::*;
+pub use word_counts::*;
 
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
 pub type Result<T> = std::result::Result<T, Error>;
@@ -44,13 +58,15 @@ pub trait Normalizer: Send + Sync {
 }
 
 #[typetag::serde(tag = "type")]
-/// The `PreTokenizer` is in charge of doing the pre-segmentation step. It splits the given string
-/// in multiple substrings, keeping track of the offsets of said substrings from the
-/// `NormalizedString`. In some occasions, the `PreTokenizer` might need to modify the given
-/// `NormalizedString` to ensure we can entirely keep track of the offsets and the mapping with
-/// the original string.
+/// The `PreTokenizer` is in charge of doing the pre-segmentation step. It splits each split of
+/// the given `PreTokenizedString` in multiple substrings. Each split is a `NormalizedString`, so
+/// the `PreTokenizer` can also modify it (like `ByteLevel` does) while keeping track of the
+/// offsets and the mapping with the original string.
+///
+/// The offsets of the splits are always expressed in chars. The `Tokenizer` converts them to the
+/// requested `OffsetUnit` at the end.
 pub trait PreTokenizer: Send + Sync {
-    fn pre_tokenize(&self, normalized: &mut NormalizedString) -> Result<Vec<(String, Offsets)>>;
+    fn pre_tokenize(&self, pretokenized: &mut PreTokenizedString) -> Result<()>;
 }
 
 #[typetag::serde(tag = "type")]
@@ -100,11 +116,20 @@ pub trait Decoder: Send + Sync {
     fn decode(&self, tokens: Vec<String>) -> Result<String>;
 }
 
+#[typetag::serde(tag = "type")]
 /// A `Trainer` has the responsibility to train a model. We feed it with lines/sentences
 /// and it returns a `Model` when done.
-pub trait Trainer: Sync {
+pub trait Trainer: Send + Sync {
     /// Whether we should show progress during the training.
     fn should_show_progress(&self) -> bool;
+    /// The callback that should receive the progress events of the training, if any.
+    fn progress_callback(&self) -> Option<&ProgressCallback> {
+        None
+    }
+    /// The token that can be used to cancel the training, if any.
+    fn cancellation_token(&self) -> Option<&CancellationToken> {
+        None
+    }
     /// The actual training method. This will return a new trained Model as well as a list
     /// of `special_tokens` to be added directly to the tokenizer along with the model.
     fn train(&self, words: HashMap<String, u32>) -> Result<(Box<dyn Model>, Vec<AddedToken>)>;
@@ -192,6 +217,8 @@ pub struct Tokenizer {
     model: Box<dyn Model>,
     post_processor: Option<Box<dyn PostProcessor>>,
     decoder: Option<Box<dyn Decoder>>,
+    // The trainer used to train the model, kept to be able to train it again
+    trainer: Option<Box<dyn Trainer>>,
 
     // Added Vocabulary capabilities
     added_vocabulary: AddedVocabulary,
@@ -199,6 +226,10 @@ pub struct Tokenizer {
     // General processing parameters
     truncation: Option<TruncationParams>,
     padding: Option<PaddingParams>,
+    offset_unit: OffsetUnit,
+
+    // Informative only
+    metadata: Option<Metadata>,
 }
 
 impl std::str::FromStr for Tokenizer {
@@ -218,11 +249,15 @@ impl Tokenizer {
             model,
             post_processor: None,
             decoder: None,
+            trainer: None,
 
             added_vocabulary: AddedVocabulary::new(),
 
             truncation: None,
             padding: None,
+            offset_unit: OffsetUnit::Char,
+
+            metadata: None,
         }
     }
 
@@ -300,6 +335,19 @@ impl Tokenizer {
         self.decoder.as_ref()
     }
 
+    /// Set the trainer. It will be saved along with the rest of the pipeline, and can be used
+    /// to train the model again using `retrain`.
+    pub fn with_trainer(&mut self, trainer: Box<dyn Trainer>) -> &Self {
+        self.trainer = Some(trainer);
+        self
+    }
+
+    /// Get the trainer
+    #[allow(clippy::borrowed_box)]
+    pub fn get_trainer(&self) -> Option<&Box<dyn Trainer>> {
+        self.trainer.as_ref()
+    }
+
     /// Set the model
     pub fn with_model(&mut self, model: Box<dyn Model>) -> &Self {
         self.model = model;
@@ -344,6 +392,35 @@ impl Tokenizer {
         self.padding.as_mut()
     }
 
+    /// Set the unit of the offsets in the produced `Encoding`. The offsets always refer to the
+    /// original input, but can be expressed in bytes (to slice a Rust `str`), chars (the default)
+    /// or UTF-16 code units (to slice a JavaScript or Java string).
+    pub fn with_offset_unit(&mut self, unit: OffsetUnit) -> &Self {
+        self.offset_unit = unit;
+        self
+    }
+
+    /// Get the unit of the offsets in the produced `Encoding`
+    pub fn get_offset_unit(&self) -> OffsetUnit {
+        self.offset_unit
+    }
+
+    /// Set the metadata
+    pub fn with_metadata(&mut self, metadata: Option<Metadata>) -> &Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Get the currently set metadata
+    pub fn get_metadata(&self) -> Option<&Metadata> {
+        self.metadata.as_ref()
+    }
+
+    /// Get a mutable reference to the currently set metadata
+    pub fn get_metadata_mut(&mut self) -> Option<&mut Metadata> {
+        self.metadata.as_mut()
+    }
+
     /// Get the vocabulary
     pub fn get_vocab(&self, with_added_tokens: bool) -> HashMap<String, u32> {
         let mut final_vocab = self.model.get_vocab().clone();
@@ -388,12 +465,11 @@ impl Tokenizer {
             .added_vocabulary
             .extract_and_normalize(self.normalizer.as_deref(), sentence)
             .into_iter()
-            .map(|(mut sentence, id)| -> Result<NormalizedString> {
+            .map(|(sentence, id)| -> Result<NormalizedString> {
                 if id.is_some() {
                     Ok(sentence)
                 } else {
-                    self.pre_tokenize(&mut sentence)?;
-                    Ok(sentence)
+                    Ok(self.pre_tokenize(sentence)?.into_normalized())
                 }
             })
             .collect::<Result<Vec<_>>>()?;
@@ -420,33 +496,37 @@ impl Tokenizer {
                 .added_vocabulary
                 .extract_and_normalize(self.normalizer.as_deref(), &subseq)
                 .into_iter()
-                .map(
-                    |(mut normalized, id)| -> Result<(Encoding, NormalizedString)> {
-                        if let Some(id) = id {
-                            Ok((
-                                Encoding::new(
-                                    vec![id],
-                                    vec![type_id],
-                                    vec![normalized.get().to_owned()],
-                                    vec![Some(0)],
-                                    vec![(0, normalized.len())],
-                                    vec![0],
-                                    vec![1],
-                                    vec![],
-                                ),
-                                normalized,
-                            ))
-                        } else {
-                            // 1. Pre tokenization
-                            let pre_tokenized = self.pre_tokenize(&mut normalized)?;
-                            // 2. Model
-                            let tokens = self.model.tokenize(pre_tokenized)?;
-                            let encoding = Encoding::from_tokens(tokens, type_id);
-
-                            Ok((encoding, normalized))
-                        }
-                    },
-                );
+                .map(|(normalized, id)| -> Result<(Encoding, NormalizedString)> {
+                    if let Some(id) = id {
+                        Ok((
+                            Encoding::new(
+                                vec![id],
+                                vec![type_id],
+                                vec![normalized.get().to_owned()],
+                                vec![Some(0)],
+                                vec![(0, normalized.len())],
+                                vec![0],
+                                vec![1],
+                                vec![],
+                            ),
+                            normalized,
+                        ))
+                    } else {
+                        // 1. Pre tokenization
+                        let pre_tokenized = self.pre_tokenize(normalized)?;
+                        // 2. Model
+                        let tokens = self.model.tokenize(
+                            pre_tokenized
+                                .get_splits(OffsetReferential::Normalized)?
+                                .into_iter()
+                                .map(|(s, offsets)| (s.to_owned(), offsets))
+                                .collect(),
+                        )?;
+                        let encoding = Encoding::from_tokens(tokens, type_id);
+
+                        Ok((encoding, pre_tokenized.into_normalized()))
+                    }
+                });
 
             let (all_encodings, all_normalized) =
                 ResultShunt::process(results, |iter| iter.unzip::<_, _, Vec<_>, Vec<_>>())?;
@@ -458,20 +538,25 @@ impl Tokenizer {
 
             let mut offset = 0; //final_normalized.len_original();
             for (mut encoding, normalized) in all_encodings.into_iter().zip(all_normalized) {
-                encoding
-                    .get_offsets_mut()
-                    .iter_mut()
-                    .for_each(|(start, end)| {
-                        // We convert offsets back to original before merging
-                        let (s, e) = normalized
-                            .convert_offsets(Range::Normalized(*start..*end))
-                            .map_or((*start, *end), |range| (range.start, range.end));
-                        *start = s + offset;
-                        *end = e + offset;
-                    });
+                let offsets = encoding.get_offsets_mut();
+                offsets.iter_mut().for_each(|(start, end)| {
+                    // We convert offsets back to original before merging
+                    let (s, e) = normalized
+                        .convert_offsets(Range::Normalized(*start..*end))
+                        .map_or((*start, *end), |range| (range.start, range.end));
+                    *start = s;
+                    *end = e;
+                });
+                // Then we express them in the requested unit
+                self.offset_unit
+                    .convert_offsets(normalized.get_original(), offsets);
+                offsets.iter_mut().for_each(|(start, end)| {
+                    *start += offset;
+                    *end += offset;
+                });
                 // We use the original length because we are merging offsets back to the
                 // original referential
-                offset += normalized.len_original();
+                offset += self.offset_unit.len_of(normalized.get_original());
 
                 final_encoding.merge_with(encoding, false);
             }
@@ -578,108 +663,188 @@ impl Tokenizer {
             .collect()
     }
 
-    /// Train a model and replace our current Model, using the given Trainer
+    /// Count the words in the given files, as the first step of the training with the given
+    /// Trainer. The result can be saved using `save_word_counts`, which allows to count words
+    /// on multiple machines, before merging everything and calling `train_from_word_counts`.
     #[allow(clippy::borrowed_box)]
-    fn word_count(
-        &mut self,
+    pub fn word_count(
+        &self,
         trainer: &Box<dyn Trainer>,
         files: Vec<String>,
     ) -> Result<HashMap<String, u32>> {
+        Ok(self.count_words(trainer, files)?.0)
+    }
+
+    /// Count the words in the given files, also returning the provenance of each file
+    #[allow(clippy::borrowed_box)]
+    fn count_words(
+        &self,
+        trainer: &Box<dyn Trainer>,
+        files: Vec<String>,
+    ) -> Result<(HashMap<String, u32>, Vec<FileProvenance>)> {
         let max_read = 1_000_000;
-        let len: u64 = files
+        let len = files
             .iter()
-            .map(|filename| File::open(filename).unwrap().metadata().unwrap().len() as u64)
-            .sum();
+            .map(|filename| Ok(File::open(filename)?.metadata()?.len()))
+            .sum::<Result<u64>>()?;
 
-        let progress = if trainer.should_show_progress() {
+        let bar = if trainer.should_show_progress() {
             let progress = ProgressBar::new(len);
             progress.set_style(
                 ProgressStyle::default_bar()
                     .template("[{elapsed_precise}] {msg:<40!} {wide_bar} {percent:>19!}"),
             );
-            progress.set_message(&format!("Reading files ({:.2} Mo)", len / 1_000_000));
-            progress.set_draw_delta(len / 100); // Redraw only every 2%
             Some(progress)
         } else {
             None
         };
-        let words = files
+        let mut progress = Progress::new(
+            bar,
+            trainer.progress_callback().cloned(),
+            trainer.cancellation_token().cloned(),
+        );
+        progress.start(
+            TrainingPhase::ReadFiles,
+            len,
+            &format!("Reading files ({:.2} Mo)", len / 1_000_000),
+        );
+        let (words, provenance) = files
             .into_iter()
-            .map(|filename| -> Result<HashMap<String, u32>> {
-                let file = File::open(filename)?;
-                let file = BufReader::with_capacity(max_read, file);
-                // We read new lines using this API instead of the Lines Iterator
-                // on purpose. We want to keep the `\n` and potential `\r` between each lines
-                // We use an iterator to be able to chain with par_bridge.
-                file.lines_with_ending()
-                    .maybe_par_bridge()
-                    .map_with(
-                        &progress,
-                        |progress, line| -> Result<HashMap<String, u32>> {
+            .map(
+                |filename| -> Result<(HashMap<String, u32>, FileProvenance)> {
+                    let file = File::open(&filename)?;
+                    let size = file.metadata()?.len();
+                    let file = BufReader::with_capacity(max_read, file);
+                    // Lines are read sequentially, and keep their endings, so we can hash the
+                    // content of the file while reading it.
+                    let mut hasher = Sha256::new();
+                    // We read new lines using this API instead of the Lines Iterator
+                    // on purpose. We want to keep the `\n` and potential `\r` between each lines
+                    // We use an iterator to be able to chain with par_bridge.
+                    // We stop reading as soon as the training gets cancelled.
+                    let words = file
+                        .lines_with_ending()
+                        .take_while(|_| !progress.is_cancelled())
+                        .inspect(|line| {
+                            if let Ok(line) = line {
+                                hasher.update(line.as_bytes());
+                            }
+                        })
+                        .maybe_par_bridge()
+                        .map(|line| -> Result<HashMap<String, u32>> {
                             let newline = line?;
                             let mut words = HashMap::new();
-                            let mut normalized =
-                                self.do_normalize(NormalizedString::from(&newline))?;
-                            let pre_tokenized = self.pre_tokenize(&mut normalized)?;
+                            let normalized = self.do_normalize(NormalizedString::from(&newline))?;
+                            let pre_tokenized = self.pre_tokenize(normalized)?;
                             trainer.process_tokens(
                                 &mut words,
-                                pre_tokenized.into_iter().map(|(t, _)| t).collect(),
+                                pre_tokenized
+                                    .get_splits(OffsetReferential::Normalized)?
+                                    .into_iter()
+                                    .map(|(t, _)| t.to_owned())
+                                    .collect(),
                             );
 
-                            let b = newline.len();
-                            if let Some(pbar) = progress {
-                                pbar.inc(b as u64);
-                            }
+                            progress.inc(newline.len() as u64);
                             Ok(words)
+                        })
+                        .reduce(
+                            || Ok(HashMap::new()),
+                            |acc, ws| {
+                                let mut acc = acc?;
+                                for (k, v) in ws? {
+                                    acc.entry(k).and_modify(|c| *c += v).or_insert(v);
+                                }
+                                Ok(acc)
+                            },
+                        )?;
+
+                    Ok((
+                        words,
+                        FileProvenance {
+                            name: filename,
+                            size,
+                            sha256: format!("{:x}", hasher.finalize()),
                         },
-                    )
-                    .reduce(
-                        || Ok(HashMap::new()),
-                        |acc, ws| {
-                            let mut acc = acc?;
-                            for (k, v) in ws? {
-                                acc.entry(k).and_modify(|c| *c += v).or_insert(v);
-                            }
-                            Ok(acc)
-                        },
-                    )
-            })
+                    ))
+                },
+            )
             .try_fold(
-                HashMap::new(),
-                |mut acc, ws| -> Result<HashMap<String, u32>> {
-                    for (k, v) in ws? {
+                (HashMap::new(), vec![]),
+                |(mut acc, mut provenance), ws| -> Result<_> {
+                    let (ws, file) = ws?;
+                    for (k, v) in ws {
                         acc.entry(k).and_modify(|c| *c += v).or_insert(v);
                     }
-                    Ok(acc)
+                    provenance.push(file);
+                    Ok((acc, provenance))
                 },
             )?;
-        if let Some(pbar) = progress {
-            pbar.finish();
-        }
-        Ok(words)
+        progress.check()?;
+        progress.finish();
+        Ok((words, provenance))
     }
 
     /// Train a model and replace our current Model, using the given Trainer
     #[allow(clippy::borrowed_box)]
     pub fn train(&mut self, trainer: &Box<dyn Trainer>, files: Vec<String>) -> Result<()> {
-        let words = self.word_count(trainer, files)?;
+        let (words, provenance) = self.count_words(trainer, files)?;
+        self.do_train(trainer, words, provenance)
+    }
 
-        let (model, special_tokens) = trainer.train(words)?;
+    /// Train a model and replace our current Model, using the Trainer that has been set with
+    /// `with_trainer`
+    pub fn retrain(&mut self, files: Vec<String>) -> Result<()> {
+        let trainer = self
+            .trainer
+            .take()
+            .ok_or("A trainer must be set to retrain the model")?;
+        let res = self.train(&trainer, files);
+        self.trainer = Some(trainer);
+        res
+    }
+
+    /// Train a model and replace our current Model, using the given Trainer and word counts
+    /// computed beforehand, usually with `word_count`
+    #[allow(clippy::borrowed_box)]
+    pub fn train_from_word_counts(
+        &mut self,
+        trainer: &Box<dyn Trainer>,
+        word_counts: HashMap<String, u32>,
+    ) -> Result<()> {
+        self.do_train(trainer, word_counts, vec![])
+    }
+
+    /// Training logic, keeping track of the provenance in the metadata
+    #[allow(clippy::borrowed_box)]
+    fn do_train(
+        &mut self,
+        trainer: &Box<dyn Trainer>,
+        word_counts: HashMap<String, u32>,
+        files: Vec<FileProvenance>,
+    ) -> Result<()> {
+        let provenance = TrainingProvenance {
+            trainer: serde_json::to_value(trainer)?,
+            files,
+            date: metadata::now(),
+        };
+
+        let (model, special_tokens) = trainer.train(word_counts)?;
         self.model = model;
         self.add_special_tokens(&special_tokens);
+        self.metadata.get_or_insert_with(Metadata::default).training = Some(provenance);
 
         Ok(())
     }
 
     /// PreTokenization logic, handling the case where there is no PreTokenizer set
-    fn pre_tokenize(
-        &self,
-        mut normalized: &mut NormalizedString,
-    ) -> Result<Vec<(String, Offsets)>> {
-        match &self.pre_tokenizer {
-            None => Ok(vec![(normalized.get().to_owned(), (0, normalized.len()))]),
-            Some(pre_tokenizer) => pre_tokenizer.pre_tokenize(&mut normalized),
+    fn pre_tokenize(&self, normalized: NormalizedString) -> Result<PreTokenizedString> {
+        let mut pre_tokenized = PreTokenizedString::from(normalized);
+        if let Some(pre_tokenizer) = &self.pre_tokenizer {
+            pre_tokenizer.pre_tokenize(&mut pre_tokenized)?;
         }
+
+        Ok(pre_tokenized)
     }
 
     /// Normalization logic, go through all normalizers
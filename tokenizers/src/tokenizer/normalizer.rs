@@ -1,3 +1,6 @@
+use crate::tokenizer::alignments::Alignments;
+use crate::tokenizer::{Offsets, Pattern, Result};
+use serde::{Deserialize, Serialize};
 use std::ops::{Bound, RangeBounds};
 use unicode_normalization_alignments::UnicodeNormalization;
 
@@ -42,6 +45,55 @@ where
     }
 }
 
+/// The unit used to express offsets in a string
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OffsetUnit {
+    /// UTF-8 bytes, to slice a Rust `str` directly
+    Byte,
+    /// Unicode scalar values (a Rust `char`). This is what the `Tokenizer` uses by default
+    Char,
+    /// UTF-16 code units, to slice the strings of JavaScript, Java, C#, ...
+    Utf16,
+}
+
+impl OffsetUnit {
+    /// Returns the length of the given char in this unit
+    fn char_len(self, c: char) -> usize {
+        match self {
+            OffsetUnit::Byte => c.len_utf8(),
+            OffsetUnit::Char => 1,
+            OffsetUnit::Utf16 => c.len_utf16(),
+        }
+    }
+
+    /// Returns the length of the given string in this unit
+    pub fn len_of(self, s: &str) -> usize {
+        match self {
+            OffsetUnit::Byte => s.len(),
+            _ => s.chars().map(|c| self.char_len(c)).sum(),
+        }
+    }
+
+    /// Converts the given offsets, expressed in chars of `s`, to this unit
+    pub fn convert_offsets(self, s: &str, offsets: &mut [Offsets]) {
+        if self == OffsetUnit::Char {
+            return;
+        }
+
+        // The position of each char (and of the end of the string) in this unit
+        let mut positions = Vec::with_capacity(s.len() + 1);
+        positions.push(0);
+        for c in s.chars() {
+            positions.push(positions[positions.len() - 1] + self.char_len(c));
+        }
+        let last = positions.len() - 1;
+        for (start, end) in offsets.iter_mut() {
+            *start = positions[std::cmp::min(*start, last)];
+            *end = positions[std::cmp::min(*end, last)];
+        }
+    }
+}
+
 /// A `NormalizedString` takes care of processing an "original" string to modify it and obtain a
 /// "normalized" string. It keeps both version of the string, alignments information between both
 /// and provides an interface to retrieve ranges of each string, using offsets from any of them.
@@ -57,7 +109,7 @@ pub struct NormalizedString {
     normalized: String,
     /// Mapping from normalized string to original one: (start, end) for each character of the
     /// normalized string
-    alignments: Vec<(usize, usize)>,
+    alignments: Alignments,
 }
 
 impl NormalizedString {
@@ -66,7 +118,7 @@ impl NormalizedString {
         NormalizedString {
             original: s.to_owned(),
             normalized: s.to_owned(),
-            alignments: (0..s.chars().count()).map(|v| (v, v + 1)).collect(),
+            alignments: Alignments::identity(s.chars().count()),
         }
     }
 
@@ -88,35 +140,19 @@ impl NormalizedString {
     {
         match range {
             Range::Original(_) => {
-                let (mut start, mut end) = (0, 0);
-                let r = range.into_full_range(self.alignments.last().map_or(0, |(_, e)| *e));
-                self.alignments
-                    .iter()
-                    .enumerate()
-                    .take_while(|(_, alignment)| r.end >= alignment.1)
-                    .for_each(|(i, alignment)| {
-                        if alignment.0 <= r.start {
-                            start = i;
-                        }
-                        if alignment.1 <= r.end {
-                            end = i + 1;
-                        }
-                    });
+                let r = range.into_full_range(self.alignments.last().map_or(0, |(_, e)| e));
+                let (start, end) = self.alignments.original_to_normalized(r);
+                Some(start..end)
+            }
+            Range::Normalized(_) => {
+                let r = range.into_full_range(self.alignments.len());
+                if r.start >= r.end || r.end > self.alignments.len() {
+                    return None;
+                }
+                let (start, _) = self.alignments.get(r.start)?;
+                let (_, end) = self.alignments.get(r.end - 1)?;
                 Some(start..end)
             }
-            Range::Normalized(_) => self
-                .alignments
-                .get(range.into_full_range(self.alignments.len()))
-                .map(|alignments| {
-                    if alignments.is_empty() {
-                        None
-                    } else {
-                        let start = alignments[0].0;
-                        let end = alignments[alignments.len() - 1].1;
-                        Some(start..end)
-                    }
-                })
-                .flatten(),
         }
     }
 
@@ -201,17 +237,15 @@ impl NormalizedString {
         // We need to shift the alignments according to the part of the original string that we
         // keep
         let alignment_shift = r_original.start;
+        let mut alignments = self
+            .alignments
+            .slice(r_normalized.start, r_normalized.end)?;
+        alignments.shift(-(alignment_shift as isize));
 
         Some(Self {
             original: get_range_of(&self.original, r_original)?.to_owned(),
-            normalized: get_range_of(&self.normalized, r_normalized.clone())?.to_owned(),
-            alignments: self
-                .alignments
-                .get(r_normalized)?
-                .to_vec()
-                .iter()
-                .map(|(start, end)| (start - alignment_shift, end - alignment_shift))
-                .collect(),
+            normalized: get_range_of(&self.normalized, r_normalized)?.to_owned(),
+            alignments,
         })
     }
 
@@ -219,7 +253,7 @@ impl NormalizedString {
     /// alignments with the new ones.
     /// This method expect an Iterator yielding each char of the new normalized string
     /// with a `change` isize equals to:
-    ///   - `1` if this is a new char
+    ///   - `1` if this is a new char, aligned with the char right before it
     ///   - `-N` if the char is right before N removed chars
     ///   - `0` if this char represents the old one (even if changed)
     /// Since it is possible that the normalized string doesn't include some of the characters at
@@ -231,28 +265,31 @@ impl NormalizedString {
     /// We treat any value above `1` as `1`.
     pub fn transform<I: Iterator<Item = (char, isize)>>(&mut self, dest: I, initial_offset: usize) {
         let mut offset = -(initial_offset as isize);
-        let (normalized, alignments): (String, Vec<_>) = dest
-            .enumerate()
-            .map(|(index, (c, changes))| {
-                // A positive offset means we added characters. So we need to remove this offset
-                // from the current index to find out the previous id
-                let idx = (index as isize - offset) as usize;
-                offset += changes;
-                let align = if changes.is_positive() {
-                    if idx < 1 {
-                        (0, 0)
-                    } else {
-                        // This is a newly inserted character, so we use the alignment from the
-                        // previous one
-                        self.alignments[idx - 1]
-                    }
-                } else {
-                    self.alignments[idx]
-                };
-                // Then we keep only the char for string reconstruction
-                (c, align)
-            })
-            .unzip();
+        let mut normalized = String::with_capacity(self.normalized.len());
+        let mut alignments = Alignments::default();
+        let mut old = self.alignments.cursor();
+        let mut previous = None;
+        for (index, (c, changes)) in dest.enumerate() {
+            // A positive offset means we added characters. So we need to remove this offset
+            // from the current index to find out the previous id
+            let idx = (index as isize - offset) as usize;
+            offset += changes;
+            let align = if changes.is_positive() {
+                // This is a newly inserted character, so we use the alignment from the
+                // previous one. We can't look it up with `idx` since the previous char may
+                // have removed some chars after it.
+                match previous {
+                    Some(align) => align,
+                    None if idx < 1 => (0, 0),
+                    None => old.get(idx - 1).unwrap(),
+                }
+            } else {
+                old.get(idx).unwrap()
+            };
+            normalized.push(c);
+            alignments.push(align);
+            previous = Some(align);
+        }
         self.alignments = alignments;
         self.normalized = normalized;
     }
@@ -310,8 +347,10 @@ impl NormalizedString {
     /// Prepend the given string to ourself
     pub fn prepend(&mut self, s: &str) -> &mut Self {
         self.normalized.insert_str(0, s);
-        #[allow(clippy::reversed_empty_ranges)]
-        self.alignments.splice(0..0, s.chars().map(|_| (0, 0)));
+        let mut alignments = Alignments::default();
+        s.chars().for_each(|_| alignments.push((0, 0)));
+        alignments.extend(&self.alignments);
+        self.alignments = alignments;
         self
     }
 
@@ -319,7 +358,7 @@ impl NormalizedString {
     pub fn append(&mut self, s: &str) -> &mut Self {
         self.normalized.push_str(s);
         let last_offset = self.alignments.last().map_or((0, 0), |o| (o.1, o.1));
-        self.alignments.extend(s.chars().map(|_| last_offset));
+        s.chars().for_each(|_| self.alignments.push(last_offset));
         self
     }
 
@@ -359,6 +398,101 @@ impl NormalizedString {
         self
     }
 
+    /// Replace anything that matches the pattern with the given content.
+    /// Each char of the content is aligned with the whole part of the original string that was
+    /// matched, so that offsets can still be converted back to the original string.
+    pub fn replace<P: Pattern>(&mut self, pattern: P, content: &str) -> Result<()> {
+        self.replace_with(pattern, |_| Some(content.to_owned()))
+    }
+
+    /// Replace anything that matches the pattern with the content returned by `f` for the
+    /// matched part. When `f` returns `None`, the match is kept as is.
+    /// Just like with `replace`, each char of the content is aligned with the whole match.
+    pub fn replace_with<P, F>(&mut self, pattern: P, mut f: F) -> Result<()>
+    where
+        P: Pattern,
+        F: FnMut(&str) -> Option<String>,
+    {
+        let matches = pattern.find_matches(&self.normalized)?;
+
+        let mut normalized = String::with_capacity(self.normalized.len());
+        let mut alignments = Alignments::default();
+        let mut char_start = 0;
+        for ((start, end), is_match) in matches {
+            let part = &self.normalized[start..end];
+            let char_end = char_start + part.chars().count();
+            let content = if is_match { f(part) } else { None };
+            if let Some(content) = content {
+                let span = (
+                    self.alignments.get(char_start).unwrap().0,
+                    self.alignments.get(char_end - 1).unwrap().1,
+                );
+                normalized.push_str(&content);
+                content.chars().for_each(|_| alignments.push(span));
+            } else {
+                normalized.push_str(part);
+                alignments.extend(&self.alignments.slice(char_start, char_end).unwrap());
+            }
+            char_start = char_end;
+        }
+
+        self.normalized = normalized;
+        self.alignments = alignments;
+        Ok(())
+    }
+
+    /// Replace some ranges of the normalized string (indexing on char) by the given
+    /// `NormalizedString`, each of them being a slice of this same range (as returned by
+    /// `slice(Range::Normalized(range))`), that might have been modified since. The ranges must
+    /// be sorted and must not overlap.
+    ///
+    /// This lets us process some parts of the normalized string independently, and then bring
+    /// them back while keeping the alignments with the original string.
+    pub fn replace_slices(&mut self, slices: Vec<(std::ops::Range<usize>, NormalizedString)>) {
+        // The byte position of each char, to extract the parts that we keep
+        let bytes = self
+            .normalized
+            .char_indices()
+            .map(|(b, _)| b)
+            .chain(std::iter::once(self.normalized.len()))
+            .collect::<Vec<_>>();
+
+        let mut normalized = String::with_capacity(self.normalized.len());
+        let mut alignments = Alignments::default();
+        let mut char_pos = 0;
+        for (range, slice) in slices {
+            normalized.push_str(&self.normalized[bytes[char_pos]..bytes[range.start]]);
+            alignments.extend(
+                &self
+                    .alignments
+                    .slice(char_pos, range.start)
+                    .expect("Slices must be sorted"),
+            );
+
+            // The alignments of the slice are relative to its part of the original string
+            let original_start = self
+                .alignments
+                .get(range.start)
+                .map_or(0, |(start, _)| start);
+            let mut slice_alignments = slice.alignments;
+            slice_alignments.shift(original_start as isize);
+            normalized.push_str(&slice.normalized);
+            alignments.extend(&slice_alignments);
+
+            char_pos = range.end;
+        }
+        normalized.push_str(&self.normalized[bytes[char_pos]..]);
+        alignments.extend(
+            &self
+                .alignments
+                .slice(char_pos, self.alignments.len())
+                .expect("Slices must be sorted"),
+        );
+
+        self.normalized = normalized;
+        self.alignments = alignments;
+    }
+
     /// Split off ourselves, returning a new Self that contains the range [at, len).
     /// self will then contain the range [0, at).
     /// The provided `at` indexes on `char` not bytes.
@@ -379,7 +513,7 @@ impl NormalizedString {
         let alignments = self.alignments.split_off(at);
 
         // Split original
-        let original_at = self.alignments.last().map(|(_, end)| *end).unwrap_or(0);
+        let original_at = self.alignments.last().map(|(_, end)| end).unwrap_or(0);
         let original_byte_index = self.original.chars().enumerate().fold(0, |acc, (i, c)| {
             if i < original_at {
                 acc + c.len_utf8()
@@ -400,12 +534,9 @@ impl NormalizedString {
     pub fn merge_with(&mut self, other: &NormalizedString) {
         self.original.push_str(&other.original);
         let len = self.len() - 1;
-        self.alignments.extend(
-            other
-                .alignments
-                .iter()
-                .map(|(start, end)| (start + len, end + len)),
-        );
+        let mut alignments = other.alignments.clone();
+        alignments.shift(len as isize);
+        self.alignments.extend(&alignments);
         self.normalized.push_str(&other.normalized);
     }
 
@@ -462,7 +593,7 @@ impl NormalizedString {
 
     /// Returns the length of the normalized string (counting chars not bytes)
     pub fn len(&self) -> usize {
-        self.normalized.chars().count()
+        self.alignments.len()
     }
 
     /// Returns the length of the original string (counting chars not bytes)
@@ -476,6 +607,16 @@ impl NormalizedString {
     }
 }
 
+/// Removes `n` chars while building the `dest` of `NormalizedString::transform`, by merging
+/// them into the last char that was not inserted. When a char has been replaced by multiple
+/// ones, the inserted chars thus stay aligned with the char they come from.
+pub(crate) fn remove_chars(dest: &mut [(char, isize)], initial_offset: &mut usize, n: usize) {
+    match dest.iter_mut().rev().find(|(_, changes)| *changes <= 0) {
+        Some((_, changes)) => *changes -= n as isize,
+        None => *initial_offset += n,
+    }
+}
+
 /// Returns a range of the given string slice, by indexing chars instead of bytes
 pub fn get_range_of<T: RangeBounds<usize>>(s: &str, range: T) -> Option<&str> {
     let len = s.chars().count();
@@ -518,7 +659,7 @@ mod tests {
         let mut n = NormalizedString::from("élégant");
         n.nfd();
         assert_eq!(
-            &n.alignments,
+            &n.alignments.to_vec(),
             &[
                 (0, 1),
                 (0, 1),
@@ -538,7 +679,7 @@ mod tests {
         let mut n = NormalizedString::from("élégant");
         n.nfd().filter(|c| !c.is_mark_nonspacing());
         assert_eq!(
-            &n.alignments,
+            &n.alignments.to_vec(),
             &[(0, 1), (1, 2), (2, 3), (3, 4), (4, 5), (5, 6), (6, 7)]
         );
     }
@@ -548,7 +689,7 @@ mod tests {
         let mut n = NormalizedString::from("élégant");
         n.filter(|c| c != 'n');
         assert_eq!(
-            &n.alignments,
+            &n.alignments.to_vec(),
             &[(0, 1), (1, 2), (2, 3), (3, 4), (4, 5), (6, 7)]
         );
     }
@@ -558,7 +699,7 @@ mod tests {
         let mut n = NormalizedString::from("élégant");
         n.nfd().filter(|c| !c.is_mark_nonspacing() && c != 'n');
         assert_eq!(
-            &n.alignments,
+            &n.alignments.to_vec(),
             &[(0, 1), (1, 2), (2, 3), (3, 4), (4, 5), (6, 7)]
         );
     }
@@ -702,7 +843,7 @@ mod tests {
         n.prepend("Hey ");
         assert_eq!(&n.normalized, "Hey there");
         assert_eq!(
-            n.alignments,
+            n.alignments.to_vec(),
             vec![
                 (0, 0),
                 (0, 0),
@@ -724,7 +865,7 @@ mod tests {
         n.append(" there");
         assert_eq!(&n.normalized, "Hey there");
         assert_eq!(
-            n.alignments,
+            n.alignments.to_vec(),
             vec![
                 (0, 1),
                 (1, 2),
@@ -750,6 +891,24 @@ mod tests {
         assert_eq!(get_range_of(&s, 17..), Some("John 👋"));
     }
 
+    #[test]
+    fn replace_slices() {
+        let mut n = NormalizedString::from("Hello my friend");
+        let mut my = n.slice(Range::Normalized(6..8)).unwrap();
+        my.uppercase();
+        let mut friend = n.slice(Range::Normalized(9..15)).unwrap();
+        friend.filter(|c| c != 'i').prepend("a ");
+        n.replace_slices(vec![(6..8, my), (9..15, friend)]);
+
+        assert_eq!(n.get(), "Hello MY a frend");
+        assert_eq!(n.get_range_original(Range::Normalized(6..8)), Some("my"));
+        assert_eq!(
+            n.get_range_original(Range::Normalized(11..16)),
+            Some("friend")
+        );
+        assert_eq!(n.convert_offsets(Range::Normalized(14..16)), Some(13..15));
+    }
+
     #[test]
     fn merge() {
         let mut s = NormalizedString::from("A sentence that will be merged");
@@ -775,7 +934,7 @@ mod tests {
             Some(NormalizedString {
                 original: "𝔾𝕠𝕠𝕕".to_string(),
                 normalized: "Good".to_string(),
-                alignments: vec![(0, 1), (1, 2), (2, 3), (3, 4)]
+                alignments: vec![(0, 1), (1, 2), (2, 3), (3, 4)].into()
             })
         );
         assert_eq!(
@@ -783,7 +942,7 @@ mod tests {
             Some(NormalizedString {
                 original: "𝔾𝕠𝕠𝕕".to_string(),
                 normalized: "Good".to_string(),
-                alignments: vec![(0, 1), (1, 2), (2, 3), (3, 4)]
+                alignments: vec![(0, 1), (1, 2), (2, 3), (3, 4)].into()
             })
         );
 
@@ -828,7 +987,7 @@ mod tests {
             Some(NormalizedString {
                 original: "𝔾𝕠𝕠𝕕".to_string(),
                 normalized: "Good".to_string(),
-                alignments: vec![(0, 1), (1, 2), (2, 3), (3, 4)]
+                alignments: vec![(0, 1), (1, 2), (2, 3), (3, 4)].into()
             })
         );
         assert_eq!(
@@ -836,7 +995,7 @@ mod tests {
             Some(NormalizedString {
                 original: "𝕞𝕠𝕣𝕟𝕚𝕟𝕘".to_string(),
                 normalized: "morning".to_string(),
-                alignments: vec![(0, 1), (1, 2), (2, 3), (3, 4), (4, 5), (5, 6), (6, 7)]
+                alignments: vec![(0, 1), (1, 2), (2, 3), (3, 4), (4, 5), (5, 6), (6, 7)].into()
             })
         );
         assert_eq!(
@@ -844,9 +1003,33 @@ mod tests {
             Some(NormalizedString {
                 original: "𝔾𝕠𝕠𝕕".to_string(),
                 normalized: "Good".to_string(),
-                alignments: vec![(0, 1), (1, 2), (2, 3), (3, 4)]
+                alignments: vec![(0, 1), (1, 2), (2, 3), (3, 4)].into()
             })
         );
         assert_eq!(s.slice_bytes(Range::Original(0..10)), None);
     }
+
+    #[test]
+    fn offset_units() {
+        let s = "aé😀b";
+        assert_eq!(OffsetUnit::Byte.len_of(s), 8);
+        assert_eq!(OffsetUnit::Char.len_of(s), 4);
+        assert_eq!(OffsetUnit::Utf16.len_of(s), 5);
+
+        let offsets = vec![(0, 1), (1, 2), (2, 3), (3, 4), (1, 4)];
+        let converted = |unit: OffsetUnit| {
+            let mut offsets = offsets.clone();
+            unit.convert_offsets(s, &mut offsets);
+            offsets
+        };
+        assert_eq!(converted(OffsetUnit::Char), offsets);
+        assert_eq!(
+            converted(OffsetUnit::Byte),
+            vec![(0, 1), (1, 3), (3, 7), (7, 8), (1, 8)]
+        );
+        assert_eq!(
+            converted(OffsetUnit::Utf16),
+            vec![(0, 1), (1, 2), (2, 4), (4, 5), (1, 5)]
+        );
+    }
 }
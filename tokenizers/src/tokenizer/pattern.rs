@@ -0,0 +1,138 @@
+use crate::tokenizer::{Offsets, Result};
+use onig::Regex;
+
+/// Pattern used to match some parts of a string
+pub trait Pattern {
+    /// Slice the given string in a list of pattern match positions, with
+    /// a boolean indicating whether this is a match or not.
+    ///
+    /// The returned offsets are expressed in bytes, and cover the whole `inside` string.
+    /// This method returns an empty Vec if `inside` is empty.
+    fn find_matches(&self, inside: &str) -> Result<Vec<(Offsets, bool)>>;
+}
+
+/// Build the list of all the splits covering `inside`, given the (non-overlapping, ordered)
+/// offsets of the matches. Empty matches are ignored.
+pub(crate) fn with_gaps<I: Iterator<Item = Offsets>>(
+    inside: &str,
+    matches: I,
+) -> Vec<(Offsets, bool)> {
+    let mut splits = vec![];
+    let mut prev = 0;
+    for (start, end) in matches {
+        if start == end {
+            continue;
+        }
+        if prev != start {
+            splits.push(((prev, start), false));
+        }
+        splits.push(((start, end), true));
+        prev = end;
+    }
+    if prev != inside.len() {
+        splits.push(((prev, inside.len()), false));
+    }
+    splits
+}
+
+impl Pattern for char {
+    fn find_matches(&self, inside: &str) -> Result<Vec<(Offsets, bool)>> {
+        let is_char = |c: char| -> bool { c == *self };
+        is_char.find_matches(inside)
+    }
+}
+
+impl Pattern for &str {
+    fn find_matches(&self, inside: &str) -> Result<Vec<(Offsets, bool)>> {
+        if self.is_empty() {
+            // If we try to find the matches with an empty string, just don't match anything
+            return Ok(with_gaps(inside, std::iter::empty()));
+        }
+
+        Ok(with_gaps(
+            inside,
+            inside
+                .match_indices(self)
+                .map(|(start, m)| (start, start + m.len())),
+        ))
+    }
+}
+
+impl Pattern for &String {
+    fn find_matches(&self, inside: &str) -> Result<Vec<(Offsets, bool)>> {
+        let s: &str = self;
+        s.find_matches(inside)
+    }
+}
+
+impl Pattern for &Regex {
+    fn find_matches(&self, inside: &str) -> Result<Vec<(Offsets, bool)>> {
+        Ok(with_gaps(inside, self.find_iter(inside)))
+    }
+}
+
+impl<F> Pattern for F
+where
+    F: Fn(char) -> bool,
+{
+    fn find_matches(&self, inside: &str) -> Result<Vec<(Offsets, bool)>> {
+        Ok(with_gaps(
+            inside,
+            inside
+                .char_indices()
+                .filter(|(_, c)| self(*c))
+                .map(|(start, c)| (start, start + c.len_utf8())),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! do_test {
+        ($inside: expr, $pattern: expr => $result: expr) => {
+            assert_eq!($pattern.find_matches($inside).unwrap(), $result);
+        };
+    }
+
+    #[test]
+    fn char() {
+        do_test!("aba", 'a' => vec![((0, 1), true), ((1, 2), false), ((2, 3), true)]);
+        do_test!("bbbba", 'a' => vec![((0, 4), false), ((4, 5), true)]);
+        do_test!("aabbb", 'a' => vec![((0, 1), true), ((1, 2), true), ((2, 5), false)]);
+        do_test!("", 'a' => Vec::<(Offsets, bool)>::new());
+        do_test!("aaa", 'b' => vec![((0, 3), false)]);
+    }
+
+    #[test]
+    fn str() {
+        do_test!("aba", "a" => vec![((0, 1), true), ((1, 2), false), ((2, 3), true)]);
+        do_test!("bbbba", "a" => vec![((0, 4), false), ((4, 5), true)]);
+        do_test!("aabbb", "ab" => vec![((0, 1), false), ((1, 3), true), ((3, 5), false)]);
+        do_test!("aabbb", "" => vec![((0, 5), false)]);
+        do_test!("", "a" => Vec::<(Offsets, bool)>::new());
+    }
+
+    #[test]
+    fn functions() {
+        let is_b = |c| c == 'b';
+        do_test!("aba", is_b => vec![((0, 1), false), ((1, 2), true), ((2, 3), false)]);
+        do_test!("aaaab", is_b => vec![((0, 4), false), ((4, 5), true)]);
+        do_test!("", is_b => Vec::<(Offsets, bool)>::new());
+    }
+
+    #[test]
+    fn regex() {
+        let is_whitespace = Regex::new(r"\s+").unwrap();
+        do_test!("a   b", &is_whitespace => vec![((0, 1), false), ((1, 4), true), ((4, 5), false)]);
+        do_test!("   a   b   ", &is_whitespace =>
+            vec![((0, 3), true), ((3, 4), false), ((4, 7), true), ((7, 8), false), ((8, 11), true)]
+        );
+        do_test!("", &is_whitespace => Vec::<(Offsets, bool)>::new());
+        do_test!("𝔾𝕠𝕠𝕕 𝕞𝕠𝕣𝕟𝕚𝕟𝕘", &is_whitespace =>
+            vec![((0, 16), false), ((16, 17), true), ((17, 45), false)]
+        );
+        do_test!("aaa", &is_whitespace => vec![((0, 3), false)]);
+    }
+}
@@ -0,0 +1,301 @@
+use crate::tokenizer::{NormalizedString, Offsets, Range, Result};
+
+/// The referential in which the offsets of the splits are expressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OffsetReferential {
+    /// Chars of the original string
+    Original,
+    /// Chars of the normalized string, including all the modifications of the splits
+    Normalized,
+}
+
+/// A part of the `PreTokenizedString`
+#[derive(Debug, Clone, PartialEq)]
+struct Split {
+    /// The position of the split in the whole normalized string, in chars
+    range: std::ops::Range<usize>,
+    /// The content of the split, aligned with its own part of the original string
+    normalized: NormalizedString,
+}
+
+/// A `NormalizedString` being split by the `PreTokenizer`s. Each split is itself a
+/// `NormalizedString`, so it can be further split or modified (like `ByteLevel` does) while
+/// keeping the alignments with the original string.
+///
+/// The whole `NormalizedString` is kept up to date with the modifications of the splits, and
+/// also contains the parts that are not part of any split anymore (like the whitespace removed
+/// by `Whitespace`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreTokenizedString {
+    normalized: NormalizedString,
+    splits: Vec<Split>,
+}
+
+impl PreTokenizedString {
+    /// Split each split using the given function, which receives the index of the split and
+    /// its `NormalizedString`. The function can modify it (using the methods that keep the
+    /// alignments, like `transform`), and returns the offsets of the new splits, in chars of the
+    /// modified `NormalizedString`. These must be sorted and must not overlap. The empty splits
+    /// are ignored, and the parts that are not covered by any split are left out.
+    pub fn split<F>(&mut self, mut split_fn: F) -> Result<()>
+    where
+        F: FnMut(usize, &mut NormalizedString) -> Result<Vec<Offsets>>,
+    {
+        let mut new_splits = Vec::with_capacity(self.splits.len());
+        let mut pieces = Vec::with_capacity(self.splits.len());
+        // How much the whole normalized string grew with the splits processed so far
+        let mut shift = 0;
+        for (
+            i,
+            Split {
+                range,
+                mut normalized,
+            },
+        ) in std::mem::take(&mut self.splits).into_iter().enumerate()
+        {
+            let offsets = split_fn(i, &mut normalized)?;
+
+            let new_start = (range.start as isize + shift) as usize;
+            for (start, end) in offsets {
+                if start >= end {
+                    continue;
+                }
+                let split = normalized
+                    .slice(Range::Normalized(start..end))
+                    .ok_or("Invalid offsets for the new split")?;
+                new_splits.push(Split {
+                    range: new_start + start..new_start + end,
+                    normalized: split,
+                });
+            }
+
+            shift += normalized.len() as isize - range.len() as isize;
+            pieces.push((range, normalized));
+        }
+
+        // Bring back the modifications of the splits
+        self.normalized.replace_slices(pieces);
+        self.splits = new_splits;
+
+        Ok(())
+    }
+
+    /// Modify each split using the given function, without splitting them any further
+    pub fn normalize<F>(&mut self, mut normalize: F) -> Result<()>
+    where
+        F: FnMut(&mut NormalizedString) -> Result<()>,
+    {
+        self.split(|_, normalized| {
+            normalize(normalized)?;
+            Ok(vec![(0, normalized.len())])
+        })
+    }
+
+    /// Returns the content of each split with its offsets in the given referential. This fails
+    /// if the offsets of a split can't be converted to the original referential.
+    pub fn get_splits(&self, referential: OffsetReferential) -> Result<Vec<(&str, Offsets)>> {
+        self.splits
+            .iter()
+            .map(|split| {
+                let offsets = match referential {
+                    OffsetReferential::Original => {
+                        let range = self
+                            .normalized
+                            .convert_offsets(Range::Normalized(split.range.clone()))
+                            .ok_or(
+                                "Unable to convert the offsets of a split to the original string",
+                            )?;
+                        (range.start, range.end)
+                    }
+                    OffsetReferential::Normalized => (split.range.start, split.range.end),
+                };
+                Ok((split.normalized.get(), offsets))
+            })
+            .collect()
+    }
+
+    /// Returns the `NormalizedString` of each split
+    pub fn get_normalized_splits(&self) -> Vec<&NormalizedString> {
+        self.splits.iter().map(|split| &split.normalized).collect()
+    }
+
+    /// Returns the whole `NormalizedString`, with all the modifications of the splits
+    pub fn get_normalized(&self) -> &NormalizedString {
+        &self.normalized
+    }
+
+    pub fn into_normalized(self) -> NormalizedString {
+        self.normalized
+    }
+
+    /// Returns the number of splits
+    pub fn len(&self) -> usize {
+        self.splits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.splits.is_empty()
+    }
+}
+
+impl From<NormalizedString> for PreTokenizedString {
+    fn from(normalized: NormalizedString) -> Self {
+        let splits = if normalized.is_empty() {
+            vec![]
+        } else {
+            vec![Split {
+                range: 0..normalized.len(),
+                normalized: normalized.clone(),
+            }]
+        };
+        Self { normalized, splits }
+    }
+}
+
+impl From<&str> for PreTokenizedString {
+    fn from(s: &str) -> Self {
+        NormalizedString::from(s).into()
+    }
+}
+
+/// Pre-tokenizes the given string, and returns its splits with their offsets in the normalized
+/// string
+#[cfg(test)]
+pub(crate) fn normalized_splits(
+    pretok: &dyn crate::tokenizer::PreTokenizer,
+    s: &str,
+) -> Vec<(String, Offsets)> {
+    let mut pretokenized = PreTokenizedString::from(s);
+    pretok.pre_tokenize(&mut pretokenized).unwrap();
+    pretokenized
+        .get_splits(OffsetReferential::Normalized)
+        .unwrap()
+        .into_iter()
+        .map(|(s, offsets)| (s.to_owned(), offsets))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Splits on the spaces, removing them
+    fn split_spaces(_: usize, normalized: &mut NormalizedString) -> Result<Vec<Offsets>> {
+        let mut splits = vec![];
+        let mut start = 0;
+        for (i, c) in normalized.get().chars().enumerate() {
+            if c == ' ' {
+                splits.push((start, i));
+                start = i + 1;
+            }
+        }
+        splits.push((start, normalized.len()));
+        Ok(splits)
+    }
+
+    #[test]
+    fn split() {
+        let mut pretokenized = PreTokenizedString::from("Héllo  wörld!");
+        pretokenized.split(split_spaces).unwrap();
+        assert_eq!(
+            pretokenized
+                .get_splits(OffsetReferential::Normalized)
+                .unwrap(),
+            vec![("Héllo", (0, 5)), ("wörld!", (7, 13))]
+        );
+        assert_eq!(pretokenized.get_normalized().get(), "Héllo  wörld!");
+
+        // The splits can be split again
+        pretokenized
+            .split(|_, normalized| {
+                let len = normalized.len();
+                Ok(vec![(0, len - 1), (len - 1, len)])
+            })
+            .unwrap();
+        assert_eq!(
+            pretokenized
+                .get_splits(OffsetReferential::Normalized)
+                .unwrap(),
+            vec![
+                ("Héll", (0, 4)),
+                ("o", (4, 5)),
+                ("wörld", (7, 12)),
+                ("!", (12, 13))
+            ]
+        );
+        assert_eq!(pretokenized.len(), 4);
+    }
+
+    #[test]
+    fn modified_splits() {
+        let mut n = NormalizedString::from("Hello  WORLD");
+        n.lowercase();
+        let mut pretokenized = PreTokenizedString::from(n);
+        pretokenized.split(split_spaces).unwrap();
+
+        // Each split gets modified independently
+        pretokenized
+            .normalize(|normalized| {
+                normalized.prepend("_");
+                let chars = normalized
+                    .get()
+                    .chars()
+                    .flat_map(|c| {
+                        if c == 'l' {
+                            vec![('l', 0), ('l', 1)]
+                        } else {
+                            vec![(c, 0)]
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                normalized.transform(chars.into_iter(), 0);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(
+            pretokenized
+                .get_splits(OffsetReferential::Normalized)
+                .unwrap(),
+            vec![("_hellllo", (0, 8)), ("_worlld", (10, 17))]
+        );
+        assert_eq!(
+            pretokenized
+                .get_splits(OffsetReferential::Original)
+                .unwrap(),
+            vec![("_hellllo", (0, 5)), ("_worlld", (7, 12))]
+        );
+        assert_eq!(pretokenized.get_normalized().get(), "_hellllo  _worlld");
+        assert_eq!(
+            pretokenized
+                .get_normalized()
+                .get_range_original(Range::Normalized(13..17)),
+            Some("RLD")
+        );
+
+        // Each split is aligned with its own part of the original string
+        let splits = pretokenized.get_normalized_splits();
+        assert_eq!(splits[1].get_original(), "WORLD");
+        assert_eq!(
+            splits[1].get_range_original(Range::Normalized(3..4)),
+            Some("R")
+        );
+    }
+
+    #[test]
+    fn empty() {
+        let mut pretokenized = PreTokenizedString::from("");
+        assert!(pretokenized.is_empty());
+        pretokenized.split(split_spaces).unwrap();
+        assert!(pretokenized
+            .get_splits(OffsetReferential::Original)
+            .unwrap()
+            .is_empty());
+
+        // Empty and invalid splits
+        let mut pretokenized = PreTokenizedString::from("a b");
+        pretokenized.split(|_, _| Ok(vec![(0, 0), (1, 1)])).unwrap();
+        assert!(pretokenized.is_empty());
+        let mut pretokenized = PreTokenizedString::from("a b");
+        assert!(pretokenized.split(|_, _| Ok(vec![(2, 4)])).is_err());
+    }
+}
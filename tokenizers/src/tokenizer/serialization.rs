@@ -1,4 +1,4 @@
-use super::{added_vocabulary::AddedTokenWithId, Tokenizer};
+use super::{added_vocabulary::AddedTokenWithId, OffsetUnit, Tokenizer};
 use crate::models::bpe::BPE;
 use serde::{
     self,
@@ -14,7 +14,11 @@ impl Serialize for Tokenizer {
     where
         S: Serializer,
     {
-        let mut tokenizer = serializer.serialize_struct("Tokenizer", 9)?;
+        let n_fields = 9
+            + (self.offset_unit != OffsetUnit::Char) as usize
+            + self.trainer.is_some() as usize
+            + self.metadata.is_some() as usize;
+        let mut tokenizer = serializer.serialize_struct("Tokenizer", n_fields)?;
 
         // Start by adding the current version
         tokenizer.serialize_field("version", SERIALIZATION_VERSION)?;
@@ -33,6 +37,17 @@ impl Serialize for Tokenizer {
         tokenizer.serialize_field("decoder", &self.decoder)?;
         tokenizer.serialize_field("model", &self.model)?;
 
+        // The offset unit, trainer and metadata are optional, so we only add them when relevant
+        if self.offset_unit != OffsetUnit::Char {
+            tokenizer.serialize_field("offset_unit", &self.offset_unit)?;
+        }
+        if let Some(trainer) = &self.trainer {
+            tokenizer.serialize_field("trainer", trainer)?;
+        }
+        if let Some(metadata) = &self.metadata {
+            tokenizer.serialize_field("metadata", metadata)?;
+        }
+
         tokenizer.end()
     }
 }
@@ -48,12 +63,15 @@ impl<'de> Deserialize<'de> for Tokenizer {
                 "version",
                 "truncation",
                 "padding",
+                "offset_unit",
                 "added_tokens",
                 "normalizer",
                 "pre_tokenizer",
                 "post_processor",
                 "decoder",
                 "model",
+                "trainer",
+                "metadata",
             ],
             TokenizerVisitor,
         )
@@ -88,6 +106,9 @@ impl<'de> Visitor<'de> for TokenizerVisitor {
                 "padding" => {
                     tokenizer.with_padding(map.next_value()?);
                 }
+                "offset_unit" => {
+                    tokenizer.with_offset_unit(map.next_value()?);
+                }
                 "added_tokens" => {
                     tokens = map.next_value()?;
                 }
@@ -114,6 +135,14 @@ impl<'de> Visitor<'de> for TokenizerVisitor {
                         tokenizer.with_post_processor(processor);
                     }
                 }
+                "trainer" => {
+                    if let Some(trainer) = map.next_value()? {
+                        tokenizer.with_trainer(trainer);
+                    }
+                }
+                "metadata" => {
+                    tokenizer.with_metadata(map.next_value()?);
+                }
                 _ => {}
             };
         }
@@ -0,0 +1,130 @@
+use super::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+};
+
+static WORD_COUNTS_VERSION: &str = "1.0";
+
+#[derive(Debug)]
+pub enum WordCountsError {
+    /// The file has been written with an unknown version of the format
+    UnknownVersion(String),
+}
+
+impl std::fmt::Display for WordCountsError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            WordCountsError::UnknownVersion(v) => {
+                write!(fmt, "Unknown word counts version '{}'", v)
+            }
+        }
+    }
+}
+impl std::error::Error for WordCountsError {}
+
+/// The content of a word counts file. Words are sorted to make the files deterministic, and
+/// easy to diff.
+#[derive(Serialize)]
+struct WordCountsRef<'a> {
+    version: &'a str,
+    word_counts: BTreeMap<&'a str, u32>,
+}
+
+#[derive(Deserialize)]
+struct WordCountsOwned {
+    version: String,
+    word_counts: HashMap<String, u32>,
+}
+
+/// Save the given word counts to a portable file, that can be loaded back with
+/// `load_word_counts` or merged with others using `merge_word_counts`.
+pub fn save_word_counts<P: AsRef<Path>>(word_counts: &HashMap<String, u32>, path: P) -> Result<()> {
+    let content = WordCountsRef {
+        version: WORD_COUNTS_VERSION,
+        word_counts: word_counts.iter().map(|(w, c)| (w.as_str(), *c)).collect(),
+    };
+
+    let mut file = BufWriter::new(File::create(path)?);
+    serde_json::to_writer(&mut file, &content)?;
+    file.flush()?;
+
+    Ok(())
+}
+
+/// Load the word counts from a file written by `save_word_counts`
+pub fn load_word_counts<P: AsRef<Path>>(path: P) -> Result<HashMap<String, u32>> {
+    let file = BufReader::new(File::open(path)?);
+    let content: WordCountsOwned = serde_json::from_reader(file)?;
+    if content.version != WORD_COUNTS_VERSION {
+        return Err(Box::new(WordCountsError::UnknownVersion(content.version)));
+    }
+
+    Ok(content.word_counts)
+}
+
+/// Load and merge the word counts from all the given files. This allows counting words on
+/// multiple shards of a dataset independently, before training on the whole.
+pub fn merge_word_counts<P: AsRef<Path>>(files: &[P]) -> Result<HashMap<String, u32>> {
+    let mut merged: HashMap<String, u32> = HashMap::new();
+    for file in files {
+        for (word, count) in load_word_counts(file)? {
+            merged
+                .entry(word)
+                .and_modify(|c| *c = c.saturating_add(count))
+                .or_insert(count);
+        }
+    }
+
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(c: &[(&str, u32)]) -> HashMap<String, u32> {
+        c.iter().map(|(w, c)| (w.to_string(), *c)).collect()
+    }
+
+    #[test]
+    fn save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("counts.json");
+
+        let word_counts = counts(&[("Hello", 2), ("world", 1), ("\n", 3)]);
+        save_word_counts(&word_counts, &path).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            r#"{"version":"1.0","word_counts":{"\n":3,"Hello":2,"world":1}}"#
+        );
+        assert_eq!(load_word_counts(&path).unwrap(), word_counts);
+    }
+
+    #[test]
+    fn unknown_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("counts.json");
+        std::fs::write(&path, r#"{"version":"0.1","word_counts":{}}"#).unwrap();
+
+        let err = load_word_counts(&path).unwrap_err();
+        assert_eq!(err.to_string(), "Unknown word counts version '0.1'");
+    }
+
+    #[test]
+    fn merge() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("first.json");
+        let second = dir.path().join("second.json");
+        save_word_counts(&counts(&[("Hello", 2), ("world", 1)]), &first).unwrap();
+        save_word_counts(&counts(&[("Hello", 1), ("there", 4)]), &second).unwrap();
+
+        assert_eq!(
+            merge_word_counts(&[first, second]).unwrap(),
+            counts(&[("Hello", 3), ("world", 1), ("there", 4)])
+        );
+    }
+}
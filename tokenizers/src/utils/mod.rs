@@ -1,4 +1,5 @@
 pub mod iter;
 pub mod padding;
 pub mod parallelism;
+pub mod progress;
 pub mod truncation;
@@ -0,0 +1,224 @@
+//!
+//! This module defines helpers to report the progress of a training, and to cancel it.
+//!
+
+use crate::tokenizer::Result;
+use indicatif::ProgressBar;
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc,
+};
+
+/// The various phases a training goes through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrainingPhase {
+    /// Reading the files and counting the words
+    ReadFiles,
+    /// Splitting each word in its initial tokens
+    TokenizeWords,
+    /// Counting the pairs of tokens
+    CountPairs,
+    /// Computing the merges
+    ComputeMerges,
+}
+
+/// A progress event, emitted while training
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrainingProgress {
+    /// The current phase
+    pub phase: TrainingPhase,
+    /// The number of units processed so far in this phase
+    pub done: u64,
+    /// The total number of units expected for this phase
+    pub total: u64,
+}
+
+/// A callback receiving the progress events of a training
+pub type ProgressCallback = Arc<dyn Fn(TrainingProgress) + Send + Sync>;
+
+/// A token that can be used to cancel a training from another thread. Any clone of a token
+/// shares the same state, so cancelling one of them cancels all of them.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Create a new token, not cancelled yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request the cancellation
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether the cancellation has been requested
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// The error returned by a training that has been cancelled
+#[derive(Debug)]
+pub struct TrainingCancelled;
+
+impl std::fmt::Display for TrainingCancelled {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "Training cancelled")
+    }
+}
+impl std::error::Error for TrainingCancelled {}
+
+/// Dispatches the progress of a training to an optional progress bar and an optional callback,
+/// while keeping track of the cancellation.
+pub(crate) struct Progress {
+    bar: Option<ProgressBar>,
+    callback: Option<ProgressCallback>,
+    cancellation: Option<CancellationToken>,
+    phase: TrainingPhase,
+    total: u64,
+    // Minimum number of units between two calls to the callback
+    delta: u64,
+    done: AtomicU64,
+}
+
+impl Progress {
+    pub fn new(
+        bar: Option<ProgressBar>,
+        callback: Option<ProgressCallback>,
+        cancellation: Option<CancellationToken>,
+    ) -> Self {
+        Self {
+            bar,
+            callback,
+            cancellation,
+            phase: TrainingPhase::ReadFiles,
+            total: 0,
+            delta: 1,
+            done: AtomicU64::new(0),
+        }
+    }
+
+    /// Start a new phase, expected to process `total` units
+    pub fn start(&mut self, phase: TrainingPhase, total: u64, message: &str) {
+        self.phase = phase;
+        self.total = total;
+        self.delta = std::cmp::max(total / 100, 1);
+        self.done.store(0, Ordering::Relaxed);
+
+        if let Some(bar) = &self.bar {
+            bar.set_message(message);
+            bar.set_length(total);
+            bar.set_draw_delta(total / 100);
+            bar.reset();
+        }
+        self.emit(0, total);
+    }
+
+    /// Mark `n` more units as processed in the current phase
+    pub fn inc(&self, n: u64) {
+        if let Some(bar) = &self.bar {
+            bar.inc(n);
+        }
+        let before = self.done.fetch_add(n, Ordering::Relaxed);
+        let after = before + n;
+        // We don't want to call the callback for each unit, so we do it every 1%
+        if after / self.delta != before / self.delta || after >= self.total {
+            self.emit(after, self.total);
+        }
+    }
+
+    /// Finish the current phase. The phase might have ended before processing everything
+    /// that was expected, so we report it as complete with what has been done.
+    pub fn finish(&mut self) {
+        let done = self.done.load(Ordering::Relaxed);
+        self.total = done;
+        if let Some(bar) = &self.bar {
+            bar.set_length(done);
+            bar.finish();
+        }
+        self.emit(done, done);
+    }
+
+    /// Whether we are displaying a progress bar
+    pub fn has_bar(&self) -> bool {
+        self.bar.is_some()
+    }
+
+    /// Whether the cancellation has been requested
+    pub fn is_cancelled(&self) -> bool {
+        match &self.cancellation {
+            Some(token) => token.is_cancelled(),
+            None => false,
+        }
+    }
+
+    /// Returns an error if the cancellation has been requested
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            Err(Box::new(TrainingCancelled))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn emit(&self, done: u64, total: u64) {
+        if let Some(callback) = &self.callback {
+            callback(TrainingProgress {
+                phase: self.phase,
+                done,
+                total,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn callback_is_throttled() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let evts = events.clone();
+        let mut progress = Progress::new(
+            None,
+            Some(Arc::new(move |p| evts.lock().unwrap().push(p))),
+            None,
+        );
+
+        progress.start(TrainingPhase::CountPairs, 1000, "Count pairs");
+        for _ in 0..1000 {
+            progress.inc(1);
+        }
+        progress.finish();
+
+        let events = events.lock().unwrap();
+        // The start, one event every 10 units, and the finish
+        assert_eq!(events.len(), 102);
+        assert_eq!(
+            events[0],
+            TrainingProgress {
+                phase: TrainingPhase::CountPairs,
+                done: 0,
+                total: 1000
+            }
+        );
+        assert_eq!(events[1].done, 10);
+        assert_eq!(events[101].done, events[101].total);
+    }
+
+    #[test]
+    fn cancellation() {
+        let token = CancellationToken::new();
+        let progress = Progress::new(None, None, Some(token.clone()));
+        assert!(progress.check().is_ok());
+
+        token.cancel();
+        let err = progress.check().unwrap_err();
+        assert!(err.downcast_ref::<TrainingCancelled>().is_some());
+    }
+}
@@ -1,7 +1,10 @@
 mod common;
 
 use common::*;
-use tokenizers::tokenizer::{get_range_of, AddedToken};
+use std::collections::HashMap;
+use tokenizers::models::wordlevel::WordLevelBuilder;
+use tokenizers::pre_tokenizers::whitespace::Whitespace;
+use tokenizers::tokenizer::{get_range_of, AddedToken, OffsetUnit, Tokenizer};
 
 macro_rules! check_offsets {
     ($input: expr, $output:expr, $offset:expr, $result:expr) => {
@@ -178,3 +181,60 @@ fn split_on_added_tokens_bert() {
         &["yesterday", "i", "saw", "a", "[MASK]", "far", "away"]
     );
 }
+
+#[test]
+fn offset_units() {
+    let input = "Héllo wörld 😀! and [NEW] 😀";
+
+    let vocab = ["[UNK]", "héllo", "wörld", "and"]
+        .iter()
+        .enumerate()
+        .map(|(i, w)| (w.to_string(), i as u32))
+        .collect::<HashMap<_, _>>();
+    let mut tokenizer = Tokenizer::new(Box::new(
+        WordLevelBuilder::new()
+            .vocab(vocab)
+            .unk_token("[UNK]".into())
+            .build(),
+    ));
+    tokenizer.with_pre_tokenizer(Box::new(Whitespace));
+    tokenizer.add_tokens(&[AddedToken::from("[NEW]", false)]);
+    let expected = vec!["Héllo", "wörld", "😀!", "and", "[NEW]", "😀"];
+
+    // Chars by default
+    assert_eq!(tokenizer.get_offset_unit(), OffsetUnit::Char);
+    let output = tokenizer.encode(input, false).unwrap();
+    let tokens = output
+        .get_offsets()
+        .iter()
+        .map(|(start, end)| get_range_of(input, *start..*end).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(tokens, expected);
+
+    // Bytes slice the str directly
+    tokenizer.with_offset_unit(OffsetUnit::Byte);
+    let output = tokenizer.encode(input, false).unwrap();
+    let tokens = output
+        .get_offsets()
+        .iter()
+        .map(|(start, end)| &input[*start..*end])
+        .collect::<Vec<_>>();
+    assert_eq!(tokens, expected);
+
+    // UTF-16 code units
+    tokenizer.with_offset_unit(OffsetUnit::Utf16);
+    let output = tokenizer.encode(input, false).unwrap();
+    let utf16 = input.encode_utf16().collect::<Vec<_>>();
+    let tokens = output
+        .get_offsets()
+        .iter()
+        .map(|(start, end)| String::from_utf16(&utf16[*start..*end]).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(tokens, expected);
+
+    // The unit is saved with the tokenizer
+    let serialized = tokenizer.to_string(false).unwrap();
+    assert!(serialized.contains(r#""offset_unit":"Utf16""#));
+    let tokenizer: Tokenizer = serialized.parse().unwrap();
+    assert_eq!(tokenizer.get_offset_unit(), OffsetUnit::Utf16);
+}
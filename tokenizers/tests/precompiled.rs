@@ -0,0 +1,108 @@
+use tokenizers::normalizers::precompiled::Precompiled;
+use tokenizers::tokenizer::{NormalizedString, Normalizer, Range};
+
+/// Read a protobuf varint, advancing `pos`
+fn read_varint(bytes: &[u8], pos: &mut usize) -> u64 {
+    let mut value = 0;
+    let mut shift = 0;
+    loop {
+        let b = bytes[*pos];
+        *pos += 1;
+        value |= ((b & 0x7f) as u64) << shift;
+        if b & 0x80 == 0 {
+            return value;
+        }
+        shift += 7;
+    }
+}
+
+/// Find the first length-delimited field with the given number in a protobuf message
+fn find_field(bytes: &[u8], field: u64) -> Option<&[u8]> {
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let key = read_varint(bytes, &mut pos);
+        match key & 0x7 {
+            0 => {
+                read_varint(bytes, &mut pos);
+            }
+            1 => pos += 8,
+            2 => {
+                let len = read_varint(bytes, &mut pos) as usize;
+                if key >> 3 == field {
+                    return Some(&bytes[pos..pos + len]);
+                }
+                pos += len;
+            }
+            5 => pos += 4,
+            wire_type => panic!("Unexpected wire type {}", wire_type),
+        }
+    }
+    None
+}
+
+/// Load the `precompiled_charsmap` of a real SentencePiece model, using the `nmt_nfkc` rules
+fn get_precompiled() -> Precompiled {
+    let model = std::fs::read("data/albert-base-v1-spiece.model")
+        .expect("Files not found, run `make test` to download these files");
+    // ModelProto.normalizer_spec (3) -> NormalizerSpec.precompiled_charsmap (2)
+    let charsmap = find_field(&model, 3)
+        .and_then(|spec| find_field(spec, 2))
+        .expect("The model doesn't contain any precompiled charsmap");
+    Precompiled::from(charsmap).unwrap()
+}
+
+fn normalize(precompiled: &Precompiled, s: &str) -> NormalizedString {
+    let mut normalized = NormalizedString::from(s);
+    precompiled.normalize(&mut normalized).unwrap();
+    normalized
+}
+
+#[test]
+fn sentencepiece_nmt_nfkc() {
+    let precompiled = get_precompiled();
+
+    // Expected outputs, as given by SentencePiece with the same model
+    let cases = vec![
+        ("Hello world", "Hello world"),
+        // Full-width forms
+        ("ＡＢＣ　１２３！", "ABC 123!"),
+        // Compatibility chars of NFKC
+        ("ﬁne", "fine"),
+        ("①②③", "123"),
+        ("㍿", "株式会社"),
+        ("x²", "x2"),
+        ("™", "TM"),
+        // NMT rules: the control chars are removed, the whitespace-like ones become spaces
+        ("a\tb\u{1}c\u{7f}", "a bc"),
+    ];
+    for (input, expected) in cases {
+        assert_eq!(
+            normalize(&precompiled, input).get(),
+            expected,
+            "{:?}",
+            input
+        );
+    }
+}
+
+#[test]
+fn sentencepiece_alignments() {
+    let precompiled = get_precompiled();
+
+    let n = normalize(&precompiled, "Ｔｈｅ ﬁne ㍿");
+    assert_eq!(n.get(), "The fine 株式会社");
+    assert_eq!(
+        n.get_range_original(Range::Normalized(0..3)),
+        Some("Ｔｈｅ")
+    );
+    assert_eq!(n.get_range_original(Range::Normalized(4..6)), Some("ﬁ"));
+    assert_eq!(n.get_range_original(Range::Normalized(9..13)), Some("㍿"));
+    assert_eq!(n.convert_offsets(Range::Original(4..5)), Some(4..6));
+
+    // An expanding rule followed by a deleting one
+    let n = normalize(&precompiled, "ﬁ\u{1}ne");
+    assert_eq!(n.get(), "fine");
+    assert_eq!(n.get_range_original(Range::Normalized(0..2)), Some("ﬁ"));
+    assert_eq!(n.get_range_original(Range::Normalized(1..2)), Some("ﬁ"));
+    assert_eq!(n.get_range_original(Range::Normalized(2..4)), Some("ne"));
+}
@@ -2,6 +2,8 @@ mod common;
 
 use common::*;
 use tokenizers::models::wordlevel::WordLevel;
+use tokenizers::models::wordpiece::WordPieceTrainer;
+use tokenizers::Tokenizer;
 
 #[test]
 fn bpe_serde() {
@@ -26,3 +28,22 @@ fn wordlevel_serde() {
     let de = serde_json::from_str(&ser).unwrap();
     assert_eq!(wordlevel, de);
 }
+
+#[test]
+fn tokenizer_with_trainer_serde() {
+    let mut tokenizer = get_empty();
+    tokenizer.with_trainer(Box::new(
+        WordPieceTrainer::builder().vocab_size(1000).build(),
+    ));
+
+    let ser = tokenizer.to_string(false).unwrap();
+    let de: Tokenizer = ser.parse().unwrap();
+    assert_eq!(
+        serde_json::to_string(de.get_trainer().unwrap()).unwrap(),
+        serde_json::to_string(tokenizer.get_trainer().unwrap()).unwrap()
+    );
+    assert_eq!(de.to_string(false).unwrap(), ser);
+
+    // The trainer is not serialized at all when not set
+    assert!(!get_empty().to_string(false).unwrap().contains("trainer"));
+}
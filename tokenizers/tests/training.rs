@@ -0,0 +1,144 @@
+use std::fs;
+use tokenizers::models::bpe::{BpeTrainer, BPE};
+use tokenizers::pre_tokenizers::whitespace::Whitespace;
+use tokenizers::tokenizer::{
+    merge_word_counts, save_word_counts, CancellationToken, Metadata, Tokenizer, Trainer,
+    TrainingCancelled,
+};
+
+fn get_tokenizer() -> Tokenizer {
+    let mut tokenizer = Tokenizer::new(Box::new(BPE::default()));
+    tokenizer.with_pre_tokenizer(Box::new(Whitespace));
+    tokenizer
+}
+
+#[test]
+fn train_from_merged_word_counts() {
+    let dir = tempfile::tempdir().unwrap();
+    let shard_a = dir.path().join("a.txt");
+    let shard_b = dir.path().join("b.txt");
+    fs::write(&shard_a, "Roses are red\nViolets are blue\n").unwrap();
+    fs::write(&shard_b, "Sugar is sweet\nAnd so are you\n").unwrap();
+
+    let trainer: Box<dyn Trainer> = Box::new(
+        BpeTrainer::builder()
+            .show_progress(false)
+            .vocab_size(40)
+            .build(),
+    );
+
+    // Count each shard independently, as different jobs would
+    let mut count_files = vec![];
+    for (i, shard) in [&shard_a, &shard_b].iter().enumerate() {
+        let counts = get_tokenizer()
+            .word_count(&trainer, vec![shard.to_str().unwrap().to_owned()])
+            .unwrap();
+        let count_file = dir.path().join(format!("counts-{}.json", i));
+        save_word_counts(&counts, &count_file).unwrap();
+        count_files.push(count_file);
+    }
+
+    let mut distributed = get_tokenizer();
+    distributed
+        .train_from_word_counts(&trainer, merge_word_counts(&count_files).unwrap())
+        .unwrap();
+
+    let mut direct = get_tokenizer();
+    direct
+        .train(
+            &trainer,
+            vec![
+                shard_a.to_str().unwrap().to_owned(),
+                shard_b.to_str().unwrap().to_owned(),
+            ],
+        )
+        .unwrap();
+
+    assert_eq!(distributed.get_vocab(true), direct.get_vocab(true));
+}
+
+#[test]
+fn cancelled_training() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("a.txt");
+    fs::write(&file, "Roses are red\nViolets are blue\n").unwrap();
+
+    let token = CancellationToken::new();
+    let trainer: Box<dyn Trainer> = Box::new(
+        BpeTrainer::builder()
+            .show_progress(false)
+            .cancellation_token(token.clone())
+            .build(),
+    );
+    token.cancel();
+
+    let err = get_tokenizer()
+        .train(&trainer, vec![file.to_str().unwrap().to_owned()])
+        .unwrap_err();
+    assert!(err.downcast_ref::<TrainingCancelled>().is_some());
+}
+
+#[test]
+fn retrain_with_stored_trainer() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("a.txt");
+    fs::write(&file, "Roses are red\nViolets are blue\n").unwrap();
+    let files = vec![file.to_str().unwrap().to_owned()];
+
+    let mut tokenizer = get_tokenizer();
+    assert!(tokenizer.retrain(files.clone()).is_err());
+
+    tokenizer.with_trainer(Box::new(
+        BpeTrainer::builder()
+            .show_progress(false)
+            .vocab_size(20)
+            .build(),
+    ));
+    let mut reloaded: Tokenizer = tokenizer.to_string(false).unwrap().parse().unwrap();
+    tokenizer.retrain(files.clone()).unwrap();
+    reloaded.retrain(files).unwrap();
+
+    assert_eq!(tokenizer.get_vocab_size(true), 20);
+    assert_eq!(tokenizer.get_vocab(true), reloaded.get_vocab(true));
+}
+
+#[test]
+fn training_provenance() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("data.txt");
+    fs::write(&file, "Roses are red\nViolets are blue\n").unwrap();
+
+    let trainer: Box<dyn Trainer> = Box::new(
+        BpeTrainer::builder()
+            .show_progress(false)
+            .vocab_size(20)
+            .build(),
+    );
+    let mut tokenizer = get_tokenizer();
+    tokenizer.with_metadata(Some(Metadata::default()));
+    tokenizer
+        .get_metadata_mut()
+        .unwrap()
+        .values
+        .insert("owner".into(), "search".into());
+    tokenizer
+        .train(&trainer, vec![file.to_str().unwrap().to_owned()])
+        .unwrap();
+
+    let metadata = tokenizer.get_metadata().unwrap();
+    assert_eq!(metadata.values["owner"], "search");
+    let training = metadata.training.as_ref().unwrap();
+    assert_eq!(training.trainer["type"], "BpeTrainer");
+    assert_eq!(training.trainer["vocab_size"], 20);
+    assert_eq!(training.files.len(), 1);
+    assert_eq!(training.files[0].name, file.to_str().unwrap());
+    assert_eq!(training.files[0].size, 31);
+    assert_eq!(
+        training.files[0].sha256,
+        "fe16da553f29a704ad4c78624bc9354b8e4df6e4de8edb5b0f8d9f9090501911"
+    );
+
+    // The metadata survives a round trip
+    let reloaded: Tokenizer = tokenizer.to_string(false).unwrap().parse().unwrap();
+    assert_eq!(reloaded.get_metadata(), tokenizer.get_metadata());
+}